@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+/// A rectangle of the sheet, shown for `duration` seconds.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+  pub x: f32,
+  pub y: f32,
+  pub w: f32,
+  pub h: f32,
+  pub duration: f32
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Clip {
+  pub frames: Vec<Frame>,
+  #[serde(default = "looping_by_default")]
+  pub looping: bool
+}
+
+fn looping_by_default() -> bool {
+  true
+}
+
+impl Clip {
+  /// The frame shown `time` seconds into the clip, a clip that doesn't loop holds its last frame.
+  pub fn frame_at(& self, time: f32) -> Option<&Frame> {
+    frame_index(self.frames.iter().map(|frame| frame.duration), self.looping, time).and_then(|index| self.frames.get(index))
+  }
+}
+
+/// Index of the frame shown `time` seconds into frames lasting `durations`, a sequence that
+/// doesn't loop holds its last frame. Clips and hitboxes both go through it, so they can't drift apart.
+pub fn frame_index(durations: impl Iterator<Item = f32> + Clone, looping: bool, time: f32) -> Option<usize> {
+  let length: f32 = durations.clone().sum();
+  let count = durations.clone().count();
+  if count == 0 {
+    return None;
+  }
+  if length <= 0f32 {
+    return Some(0);
+  }
+
+  let mut time = if looping { time % length } else { time.min(length) };
+  for (index, duration) in durations.enumerate() {
+    if time < duration {
+      return Some(index);
+    }
+    time -= duration;
+  }
+
+  Some(count - 1)
+}
+
+/// The frame-description file next to a sheet: the image to use and its named clips.
+#[derive(Deserialize)]
+struct Description {
+  texture: String,
+  clips: HashMap<String, Clip>
+}
+
+pub struct SpriteSheet {
+  texture: Texture2D,
+  clips: HashMap<String, Clip>
+}
+
+impl SpriteSheet {
+  /// Loads a frame description and the texture it points to, relative to the description.
+  pub async fn load(path: &str) -> Result<Self, String> {
+    let text = load_string(path).await.map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+    let description: Description = ron::from_str(&text).map_err(|e| format!("Couldn't parse {}: {}", path, e))?;
+
+    let texture_path = Path::new(path).with_file_name(&description.texture);
+    let texture_path = texture_path.to_string_lossy();
+    let texture = load_texture(&texture_path).await.map_err(|e| format!("Couldn't load {}: {}", texture_path, e))?;
+    texture.set_filter(FilterMode::Nearest);
+
+    Ok(Self { texture, clips: description.clips })
+  }
+
+  pub fn frame(& self, clip: &str, time: f32) -> Option<&Frame> {
+    self.clips.get(clip).and_then(|clip| clip.frame_at(time))
+  }
+
+  /// Frame number `index` of a clip.
+  pub fn frame_by_index(& self, clip: &str, index: usize) -> Option<&Frame> {
+    self.clips.get(clip).and_then(|clip| clip.frames.get(index))
+  }
+
+  /// Every clip of the sheet, with its number of frames.
+  pub fn frame_counts(& self) -> impl Iterator<Item = (&str, usize)> {
+    self.clips.iter().map(|(name, clip)| (name.as_str(), clip.frames.len()))
+  }
+
+  /// Draws a frame at its own size, standing on the bottom middle of `anchor`.
+  pub fn draw(& self, frame: &Frame, anchor: Rect, color: Color) {
+    let x = anchor.x + (anchor.w - frame.w) * 0.5f32;
+    let y = anchor.bottom() - frame.h;
+    draw_texture_ex(self.texture, x, y, color, DrawTextureParams {
+      source: Some(Rect::new(frame.x, frame.y, frame.w, frame.h)),
+      ..Default::default()
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{frame_index, Clip, Description, Frame};
+
+  fn frame(x: f32, duration: f32) -> Frame {
+    Frame { x, y: 0f32, w: 10f32, h: 10f32, duration }
+  }
+
+  #[test]
+  fn looping_clips_wrap_around() {
+    let clip = Clip { frames: vec!(frame(0f32, 0.1f32), frame(10f32, 0.2f32)), looping: true };
+
+    assert_eq!(clip.frame_at(0.05f32).unwrap().x, 0f32);
+    assert_eq!(clip.frame_at(0.15f32).unwrap().x, 10f32);
+    assert_eq!(clip.frame_at(0.35f32).unwrap().x, 0f32);
+  }
+
+  #[test]
+  fn other_clips_hold_their_last_frame() {
+    let clip = Clip { frames: vec!(frame(0f32, 0.1f32), frame(10f32, 0.1f32)), looping: false };
+
+    assert_eq!(clip.frame_at(5f32).unwrap().x, 10f32);
+  }
+
+  #[test]
+  fn indices_follow_durations() {
+    let durations = [0.1f32, 0.2f32];
+
+    assert_eq!(frame_index(durations.into_iter(), true, 0.25f32), Some(1));
+    assert_eq!(frame_index(durations.into_iter(), true, 0.35f32), Some(0));
+    assert_eq!(frame_index(durations.into_iter(), false, 0.35f32), Some(1));
+    assert_eq!(frame_index([].into_iter(), true, 0f32), None);
+  }
+
+  #[test]
+  fn parses_frame_descriptions() {
+    let text = r#"(
+      texture: "dino.png",
+      clips: {
+        "run": (frames: [(x: 0, y: 0, w: 50, h: 120, duration: 0.1)]),
+        "dead": (looping: false, frames: []),
+      },
+    )"#;
+
+    let description: Description = ron::from_str(text).unwrap();
+
+    assert_eq!(description.texture, "dino.png");
+    assert!(description.clips["run"].looping);
+    assert!(!description.clips["dead"].looping);
+    assert!(description.clips["dead"].frame_at(0f32).is_none());
+  }
+}
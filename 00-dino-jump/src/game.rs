@@ -1,82 +1,609 @@
+use std::path::PathBuf;
+
 use macroquad::{prelude::*};
 
+use crate::options::Options;
+
 mod actors;
-use actors::dino::Dino;
-use actors::spawner::Spawner;
+pub mod audio;
+pub mod benchmark;
+pub mod bot;
+mod collision;
+pub mod controller;
+mod controls;
+pub mod difficulty;
+mod events;
+pub mod genome;
+mod ghost;
+mod high_scores;
+mod lane;
+pub mod replay;
+mod rng;
+mod screens;
+mod settings;
+pub mod simulation;
+pub mod stats;
+pub mod sprites;
+mod storage;
+mod themes;
+pub mod training;
+use actors::scene::{self, Scene, Setup};
+use audio::{Audio, Music};
+use high_scores::{HighScores, MAX_NAME_LENGTH};
+use collision::Hitboxes;
+use bot::Bot;
+use controller::{Controller, Keyboard};
+use controls::{Action, Controls};
+use events::Event;
+use genome::{Genome, NeuralBot};
+use lane::{Lane, Outcome};
+use replay::{Player, Replay};
+use screens::{Screen, ScreenStack, SettingsMenu};
+use settings::Settings;
+use stats::{Achievement, Recorder, Stats};
+use simulation::Viewport;
+pub use rng::random_seed;
+use sprites::Sprites;
+use themes::{Palette, ThemeCycle, Themes};
 
-enum State {
-  Playing,
-  GameOver
-}
+/// Logical size of the playfield: the game is drawn at this size, then scaled to fit the window.
+pub const CANVAS: Viewport = Viewport { width: 800f32, height: 600f32 };
+/// Playfield of each player in a versus race, stacked from the top of the canvas.
+pub const LANE: Viewport = Viewport { width: 800f32, height: 400f32 };
+
+/// The simulation always advances in whole ticks of this rate, whatever the frame rate is.
+pub const TICKS_PER_SECOND: u32 = 120;
+pub const TICK: f32 = 1f32 / TICKS_PER_SECOND as f32;
 
+// Past this, we drop frame time rather than trying to catch up (e.g. after a window drag).
+const MAX_FRAME_TIME: f32 = 0.25f32;
+
+/// Front-end of the game: feeds a controller (the player, a replay, the bot or a trained network) to the simulation and draws it,
+/// with the menus on a stack of screens around it. A versus race runs one lane per player instead of one.
 pub struct Context {
-  state: State,
-  score: u32,
-  dino: Dino,
-  spawner: Spawner,
-  timer: f32
+  screens: ScreenStack,
+  settings: Settings,
+  controls: Controls,
+  settings_menu: SettingsMenu,
+  /// The first lane is player 1's, the only one outside of versus races.
+  lanes: Vec<Lane>,
+  /// Trained network standing in for player 2 in versus races.
+  opponent: Option<Genome>,
+  theme: ThemeCycle,
+  canvas: RenderTarget,
+  sprites: Sprites,
+  audio: Audio,
+  /// Actors beside the simulation, like the ghost.
+  scene: Scene,
+  recording: Replay,
+  record_path: Option<PathBuf>,
+  pinned_seed: Option<u64>,
+  high_scores: HighScores,
+  stats: Stats,
+  /// Follows the player's current run.
+  run_stats: Recorder,
+  /// Achievements the last run unlocked.
+  unlocked: Vec<Achievement>,
+  name_entry: Option<String>,
+  last_rank: Option<usize>,
+  show_hitboxes: bool,
+  accumulator: f32
 }
 
 impl Context {
-  pub fn new() -> Self {
-    Self {
-      state: State::Playing,
-      score: 0,
-      dino: Dino::new(),
-      spawner: Spawner::new(),
-      timer: 0f32
+  pub fn new(options: &Options, replay: Option<Replay>, genome: Option<Genome>, opponent: Option<Genome>, sprites: Sprites, audio: Audio) -> Self {
+    let settings = Settings::load();
+    
+    // A replay or a bot plays right away, the keyboard starts on the title screen.
+    let (viewport, seed, preset, root, controller): (_, _, _, _, Box<dyn Controller>) = match replay {
+      Some(replay) => (replay.viewport, replay.seed, replay.preset, Screen::Playing, Box::new(Player::new(replay))),
+      None => {
+        let controller: Box<dyn Controller> = match genome {
+          Some(genome) => Box::new(NeuralBot::new(genome)),
+          None if options.bot => Box::new(Bot::default()),
+          None => Box::new(Keyboard::default())
+        };
+        (
+          CANVAS,
+          options.seed.unwrap_or_else(random_seed),
+          settings.difficulty,
+          if controller.name().is_some() { Screen::Playing } else { Screen::Title },
+          controller
+        )
+      }
+    };
+    
+    let theme = ThemeCycle::new(Themes::load());
+    let mut lane = Lane::new(viewport, seed, preset, controller, &theme.palette());
+    if let Some(replay) = lane.controller.replay() {
+      replay.configure(&mut lane.simulation);
     }
+    // Replays recorded before the canvas existed keep the window size they were played at.
+    let canvas = canvas(viewport);
+    
+    let mut context = Self {
+      screens: ScreenStack::new(root),
+      settings,
+      controls: Controls::new(),
+      settings_menu: SettingsMenu::new(),
+      lanes: vec!(lane),
+      opponent,
+      theme,
+      canvas,
+      sprites,
+      audio,
+      scene: Scene::default(),
+      recording: Replay::new(seed, viewport, preset),
+      record_path: options.record.clone(),
+      pinned_seed: options.seed,
+      high_scores: HighScores::load(),
+      stats: Stats::load(),
+      run_stats: Recorder::default(),
+      unlocked: vec!(),
+      name_entry: None,
+      last_rank: None,
+      show_hitboxes: false,
+      accumulator: 0f32
+    };
+    context.load_scene();
+    context
   }
   
-  pub fn reset(&mut self) {
-    self.score = 0;
-    self.state = State::Playing;
-    self.spawner.reset();
+  /// Draws the game on its canvas, then the canvas as large as it fits in the window, centered.
+  pub fn draw(& self) {
+    self.draw_canvas();
+    
+    set_default_camera();
+    clear_background(BLACK);
+    let viewport = self.canvas_viewport();
+    let scale = (screen_width() / viewport.width).min(screen_height() / viewport.height);
+    let size = vec2(viewport.width, viewport.height) * scale;
+    draw_texture_ex(self.canvas.texture, (screen_width() - size.x) * 0.5f32, (screen_height() - size.y) * 0.5f32, WHITE, DrawTextureParams {
+      dest_size: Some(size),
+      // Render targets come out upside down.
+      flip_y: true,
+      ..Default::default()
+    });
   }
   
-  pub fn game_over(&mut self) {
-    self.state = State::GameOver;
+  /// Lanes stacked from the top, one per player.
+  fn canvas_viewport(& self) -> Viewport {
+    let lane = self.lanes[0].simulation.viewport();
+    Viewport { width: lane.width, height: lane.height * self.lanes.len() as f32 }
   }
   
-  pub fn draw(& self) {
-    self.dino.draw();
-    self.spawner.draw_obstacles();
+  // Draws on the canvas from `top` down, in the coordinates of a lane.
+  fn set_canvas_camera(& self, top: f32) {
+    let viewport = self.canvas_viewport();
+    let mut camera = Camera2D::from_display_rect(Rect::new(0f32, -top, viewport.width, viewport.height));
+    camera.render_target = Some(self.canvas);
+    set_camera(&camera);
+  }
+  
+  fn draw_canvas(& self) {
+    let palette = self.theme.palette();
+    self.set_canvas_camera(0f32);
+    clear_background(palette.background);
+    
+    for (index, lane) in self.lanes.iter().enumerate() {
+      let viewport = lane.simulation.viewport();
+      self.set_canvas_camera(viewport.height * index as f32);
+      lane.background.draw();
+      draw_line(0f32, viewport.ground(), viewport.width, viewport.ground(), 2f32, palette.ground);
+      if index > 0 {
+        draw_line(0f32, 0f32, viewport.width, 0f32, 4f32, palette.ground);
+      }
+      
+      // The scene follows the first lane.
+      let scene = self.scene.actors().filter(|_| index == 0);
+      scene::draw(lane.simulation.actors().chain(scene), &self.sprites, &palette);
+      
+      if self.show_hitboxes {
+        let hitboxes = Hitboxes::builtin();
+        let scene = self.scene.actors().filter(|_| index == 0);
+        for collider in lane.simulation.actors().chain(scene).filter_map(|actor| actor.collider()) {
+          collision::draw(hitboxes, collider);
+        }
+      }
+    }
     
-    match self.state {
-      State::Playing => {
-        let score_text = std::fmt::format(format_args!("Score: {}", self.score));
-        draw_text(&score_text, 10f32, 48f32, 48f32, BLACK);
+    self.set_canvas_camera(0f32);
+    match self.screens.top() {
+      Screen::Settings => {
+        if let Some(below) = self.screens.below() {
+          self.draw_screen(below, &palette);
+        }
+        self.settings_menu.draw(&self.settings, &self.canvas_viewport(), &palette);
       },
-      _ => {}
+      screen => self.draw_screen(screen, &palette)
     }
   }
   
-  pub fn update(&mut self) {
-    let dt = get_frame_time();
-    
-    self.timer += dt;
-    
-    match self.state {
-      State::Playing => {
-        
-        self.dino.update(dt);
+  fn draw_screen(& self, screen: Screen, palette: &Palette) {
+    match screen {
+      Screen::Title => {
+        draw_text("Dino Jump", 10f32, 64f32, 64f32, palette.text);
+        let prompt = std::fmt::format(format_args!("Press {} to play, V for versus, S for settings", self.binding_name(Action::Restart)));
+        draw_text(&prompt, 10f32, 112f32, 32f32, palette.text);
+        self.draw_high_scores(10f32, 176f32, palette);
+        self.draw_stats(palette);
+      },
+      Screen::Playing => self.draw_hud(palette),
+      Screen::Paused => {
+        self.draw_hud(palette);
+        draw_text("Paused", 10f32, 96f32, 48f32, palette.text);
+        let prompt = std::fmt::format(format_args!(
+          "{} to resume, S for settings, Q to quit to the title", self.binding_name(Action::Pause)
+        ));
+        draw_text(&prompt, 10f32, 136f32, 32f32, palette.text);
+      },
+      Screen::GameOver => {
+        self.draw_hud(palette);
+        let result = match lane::outcome(self.lanes.iter().map(|lane| &lane.simulation)) {
+          _ if !self.versus() => "Game over".to_owned(),
+          Some(Outcome::Winner(index)) => std::fmt::format(format_args!("Player {} wins", index + 1)),
+          _ => "Draw".to_owned()
+        };
+        let seed_text = std::fmt::format(format_args!("{} - seed {}", result, self.lanes[0].simulation.seed()));
+        draw_text(&seed_text, 10f32, 96f32, 32f32, palette.text);
         
-        // Collision
-        if self.spawner.any_child_collided(& self.dino) {
-          self.game_over();
+        match &self.name_entry {
+          Some(name) => {
+            let prompt = std::fmt::format(format_args!("New high score! Your name: {}_", name));
+            draw_text(&prompt, 10f32, 128f32, 32f32, palette.text);
+          },
+          None => {
+            let prompt = std::fmt::format(format_args!(
+              "Press {} to retry, Escape for the title", self.binding_name(Action::Restart)
+            ));
+            draw_text(&prompt, 10f32, 128f32, 32f32, palette.text);
+          }
         }
         
-        let has_avoided = self.spawner.update(dt);
-        
-        if has_avoided {
-          self.score += 10;
+        if !self.unlocked.is_empty() {
+          let names: Vec<String> = self.unlocked.iter()
+            .map(|achievement| std::fmt::format(format_args!("{} ({})", achievement.name(), achievement.description())))
+            .collect();
+          let unlocked_text = std::fmt::format(format_args!("Unlocked: {}", names.join(", ")));
+          draw_text(&unlocked_text, 10f32, self.canvas_viewport().height - 20f32, 24f32, palette.highlight);
+        }
+        if !self.versus() {
+          self.draw_high_scores(10f32, 176f32, palette);
         }
       },
-      _ => {
-        if is_key_pressed(KeyCode::Space) {
-          self.reset();
+      Screen::Settings => ()
+    }
+  }
+  
+  fn binding_name(& self, action: Action) -> String {
+    self.settings.bindings.of(action).first().map(|binding| binding.name()).unwrap_or_else(|| "Escape".to_owned())
+  }
+  
+  // Every lane has its own, at the top of the lane.
+  fn draw_hud(& self, palette: &Palette) {
+    for (index, lane) in self.lanes.iter().enumerate() {
+      let viewport = lane.simulation.viewport();
+      let y = viewport.height * index as f32 + 48f32;
+      let score_text = match self.versus() {
+        true => std::fmt::format(format_args!("Player {}: {}", index + 1, lane.simulation.score())),
+        false => std::fmt::format(format_args!("Score: {}", lane.simulation.score()))
+      };
+      draw_text(&score_text, 10f32, y, 48f32, palette.text);
+      
+      // Active power-ups, right after the score.
+      let mut x = 10f32 + measure_text(&score_text, None, 48, 1f32).width + 24f32;
+      for effect in lane.simulation.dino().effects().iter() {
+        let seconds = (effect.ticks_left as f32 * TICK).ceil();
+        let effect_text = std::fmt::format(format_args!("{} {}s", effect.kind.name(), seconds));
+        draw_text(&effect_text, x, y, 28f32, palette.highlight);
+        x += measure_text(&effect_text, None, 28, 1f32).width + 16f32;
+      }
+      
+      if let Some(name) = lane.controller.name() {
+        draw_text(name, viewport.width - 120f32, y, 32f32, palette.text);
+      }
+    }
+    
+    if let Some(best_score) = self.scene.score_to_beat() {
+      let ghost_text = std::fmt::format(format_args!("Best: {}", best_score));
+      let width = measure_text(&ghost_text, None, 28, 1f32).width;
+      draw_text(&ghost_text, self.canvas_viewport().width - width - 10f32, 80f32, 28f32, palette.text);
+    }
+  }
+  
+  fn draw_high_scores(& self, x: f32, y: f32, palette: &Palette) {
+    draw_text("High scores", x, y, 32f32, palette.text);
+    
+    for (rank, entry) in self.high_scores.entries().iter().enumerate() {
+      let color = if Some(rank) == self.last_rank { palette.highlight } else { palette.text };
+      let line = std::fmt::format(format_args!(
+        "{:>2}. {:<12} {:>6}  {}", rank + 1, entry.name, entry.score, high_scores::format_date(entry.date)
+      ));
+      draw_text(&line, x, y + 28f32 * (rank + 1) as f32, 24f32, color);
+    }
+  }
+  
+  fn draw_stats(& self, palette: &Palette) {
+    let lifetime = self.stats.lifetime();
+    let line = std::fmt::format(format_args!(
+      "{} runs, {} min played, {} near misses - achievements {}/{}",
+      lifetime.runs, (lifetime.seconds / 60f32).floor(), lifetime.near_misses, self.stats.achievements().len(), Achievement::ALL.len()
+    ));
+    draw_text(&line, 10f32, self.canvas_viewport().height - 20f32, 24f32, palette.text);
+  }
+  
+  pub fn update(&mut self) {
+    let dt = get_frame_time().min(MAX_FRAME_TIME);
+    let screen = self.screens.top();
+    self.controls.poll();
+    
+    // The world stands still behind the pause menu and the settings.
+    if !matches!(screen, Screen::Paused | Screen::Settings) {
+      let score = self.lanes.iter().map(|lane| lane.simulation.score()).max().unwrap_or(0);
+      self.theme.update(score, dt);
+      let palette = self.theme.palette();
+      for lane in self.lanes.iter_mut() {
+        lane.background.recolor(&[palette.clouds, palette.far_mountains, palette.near_mountains, palette.dust]);
+      }
+    }
+    
+    if is_key_pressed(KeyCode::F1) {
+      self.show_hitboxes = !self.show_hitboxes;
+    }
+    
+    if is_key_pressed(KeyCode::M) && self.name_entry.is_none() && screen != Screen::Settings {
+      self.settings.muted = !self.settings.muted;
+      if let Err(message) = self.settings.save() {
+        eprintln!("{}", message);
+      }
+    }
+    self.audio.set_volume(if self.settings.muted { 0f32 } else { self.settings.volume });
+    
+    // The music follows the run, quieter while it's paused. Lanes of a race all speed up together.
+    let run_screen = match screen {
+      Screen::Settings => self.screens.below().unwrap_or(Screen::Title),
+      screen => screen
+    };
+    self.audio.update_music(match run_screen {
+      Screen::Playing | Screen::Paused => Music::Playing { intensity: self.lanes[0].simulation.intensity(), paused: screen != Screen::Playing },
+      _ => Music::Stopped
+    });
+    
+    match screen {
+      Screen::Title => self.update_title(),
+      Screen::Playing => self.update_playing(dt),
+      Screen::Paused => self.update_paused(),
+      Screen::GameOver => self.update_game_over(dt),
+      Screen::Settings => self.update_settings()
+    }
+  }
+  
+  fn update_title(&mut self) {
+    // Only a player on the keyboard can bring a second one along.
+    let human = self.lanes[0].controller.name().is_none();
+    if self.controls.is_pressed(&self.settings.bindings, Action::Restart) {
+      self.set_players(1);
+      self.restart();
+      self.screens.replace(Screen::Playing);
+    } else if is_key_pressed(KeyCode::V) && human {
+      self.set_players(2);
+      self.restart();
+      self.screens.replace(Screen::Playing);
+    } else if is_key_pressed(KeyCode::S) {
+      self.screens.push(Screen::Settings);
+    }
+  }
+  
+  fn update_playing(&mut self, dt: f32) {
+    if self.controls.is_pressed(&self.settings.bindings, Action::Pause) {
+      self.screens.push(Screen::Paused);
+      return;
+    }
+    
+    self.accumulator += dt;
+    for (index, lane) in self.lanes.iter_mut().enumerate() {
+      lane.controller.poll(&self.controls, self.settings.player_bindings(index));
+    }
+    
+    while self.accumulator >= TICK {
+      // Every lane plays the tick before anyone's crash is looked at, so nobody loses by a tick.
+      let mut crashed = false;
+      for (index, lane) in self.lanes.iter_mut().enumerate() {
+        let input = lane.controller.input(&lane.simulation);
+        if index == 0 {
+          self.recording.record(lane.simulation.ticks(), &input);
+        }
+        lane.simulation.step(&input);
+        if index == 0 {
+          self.run_stats.observe(&lane.simulation);
         }
+        self.audio.play(lane.simulation.events());
+        lane.background.scroll(lane.simulation.speed() * TICK);
+        crashed |= lane.simulation.events().iter().any(|event| matches!(event, Event::Collision(_)));
+      }
+      self.scene.update(&self.lanes[0].simulation.world());
+      self.accumulator -= TICK;
+      
+      if crashed {
+        if !self.versus() {
+          self.save_recording();
+        }
+        self.screens.replace(Screen::GameOver);
+        self.accumulator = 0f32;
+        break;
+      }
+    }
+  }
+  
+  fn update_paused(&mut self) {
+    if self.controls.is_pressed(&self.settings.bindings, Action::Pause) || is_key_pressed(KeyCode::Escape) {
+      self.screens.pop();
+    } else if is_key_pressed(KeyCode::S) {
+      self.screens.push(Screen::Settings);
+    } else if is_key_pressed(KeyCode::Q) {
+      self.record_stats();
+      self.screens.reset(Screen::Title);
+    }
+  }
+  
+  fn update_game_over(&mut self, dt: f32) {
+    // Crashed dinos play their clip out at the simulation's rate.
+    self.accumulator += dt;
+    while self.accumulator >= TICK {
+      for lane in self.lanes.iter_mut() {
+        lane.simulation.animate();
+      }
+      self.accumulator -= TICK;
+    }
+    
+    if self.name_entry.is_some() {
+      self.update_name_entry();
+    } else if self.controls.is_pressed(&self.settings.bindings, Action::Restart) {
+      self.restart();
+      self.screens.replace(Screen::Playing);
+    } else if is_key_pressed(KeyCode::Escape) {
+      self.screens.reset(Screen::Title);
+    }
+  }
+  
+  fn update_settings(&mut self) {
+    if self.settings_menu.update(&mut self.settings, &self.controls) {
+      if let Err(message) = self.settings.save() {
+        eprintln!("{}", message);
+      }
+      self.screens.pop();
+    }
+  }
+  
+  fn versus(& self) -> bool {
+    self.lanes.len() > 1
+  }
+  
+  // Player 1 keeps their controller, the others play on the keyboard, or are the trained opponent.
+  // A race stacks smaller lanes on the canvas.
+  fn set_players(&mut self, players: usize) {
+    if self.lanes.len() == players {
+      return;
+    }
+    
+    let viewport = if players > 1 { LANE } else { CANVAS };
+    let (seed, preset) = (self.lanes[0].simulation.seed(), self.lanes[0].simulation.preset());
+    let palette = self.theme.palette();
+    let mut controllers: Vec<Box<dyn Controller>> = self.lanes.drain(..).take(1).map(|lane| lane.controller).collect();
+    let opponent = self.opponent.clone();
+    controllers.resize_with(players, || -> Box<dyn Controller> {
+      match &opponent {
+        Some(genome) => Box::new(NeuralBot::new(genome.clone())),
+        None => Box::new(Keyboard::default())
+      }
+    });
+    self.lanes = controllers.into_iter().map(|controller| Lane::new(viewport, seed, preset, controller, &palette)).collect();
+    
+    self.canvas.delete();
+    self.canvas = canvas(self.canvas_viewport());
+  }
+  
+  // A seed given on the command line is kept for every retry, a replay always restarts itself.
+  // Lanes of a race all start on the same seed.
+  fn restart(&mut self) {
+    let (seed, preset) = match self.lanes[0].controller.replay() {
+      Some(replay) => (replay.seed, replay.preset),
+      None => (self.pinned_seed.unwrap_or_else(random_seed), self.settings.difficulty)
+    };
+    for lane in self.lanes.iter_mut() {
+      lane.controller.restart();
+      match lane.controller.replay() {
+        Some(replay) => replay.configure(&mut lane.simulation),
+        None => lane.simulation.set_preset(preset)
       }
+      lane.simulation.reset(seed);
     }
+    
+    self.recording = Replay::new(seed, self.lanes[0].simulation.viewport(), preset);
+    self.accumulator = 0f32;
+    self.last_rank = None;
+    self.run_stats = Recorder::default();
+    self.unlocked.clear();
+    self.load_scene();
   }
+  
+  fn load_scene(&mut self) {
+    let lane = &self.lanes[0];
+    let rival = self.versus() || lane.controller.replay().is_some();
+    self.scene.fill(&Setup { simulation: &lane.simulation, settings: &self.settings, rival });
+  }
+  
+  fn save_recording(&mut self) {
+    let Lane { simulation, controller, .. } = &self.lanes[0];
+    self.recording.finish(simulation);
+    
+    if controller.replay().is_none() {
+      if let Some(path) = &self.record_path {
+        if let Err(message) = self.recording.save(path) {
+          eprintln!("{}", message);
+        }
+      }
+    }
+    
+    // Only the player's own runs become ghosts.
+    if controller.name().is_none() {
+      if let Err(message) = ghost::save(&self.recording) {
+        eprintln!("{}", message);
+      }
+    }
+    
+    self.record_stats();
+    
+    let Lane { simulation, controller, .. } = &self.lanes[0];
+    if controller.name().is_none() && self.high_scores.qualifies(simulation.score()) {
+      // Characters typed during the run are still queued up.
+      while get_char_pressed().is_some() {}
+      self.name_entry = Some(String::new());
+    }
+  }
+  
+  // Only the player's own runs count, races aside.
+  fn record_stats(&mut self) {
+    let Lane { simulation, controller, .. } = &self.lanes[0];
+    if self.versus() || controller.name().is_some() {
+      return;
+    }
+    
+    self.unlocked = self.stats.record(self.run_stats.finish(simulation));
+    if let Err(message) = self.stats.save() {
+      eprintln!("{}", message);
+    }
+  }
+  
+  fn update_name_entry(&mut self) {
+    let Some(name) = &mut self.name_entry else { return };
+    
+    while let Some(character) = get_char_pressed() {
+      if character.is_alphanumeric() && name.chars().count() < MAX_NAME_LENGTH {
+        name.push(character);
+      }
+    }
+    
+    if is_key_pressed(KeyCode::Backspace) {
+      name.pop();
+    }
+    
+    if is_key_pressed(KeyCode::Enter) {
+      let name = if name.is_empty() { "???".to_owned() } else { name.to_owned() };
+      let simulation = &self.lanes[0].simulation;
+      let entry = high_scores::Entry::new(&name, simulation.score(), simulation.seed());
+      
+      self.last_rank = self.high_scores.insert(entry);
+      self.name_entry = None;
+      if let Err(message) = self.high_scores.save() {
+        eprintln!("{}", message);
+      }
+    }
+  }
+}
+
+fn canvas(viewport: Viewport) -> RenderTarget {
+  let canvas = render_target(viewport.width as u32, viewport.height as u32);
+  canvas.texture.set_filter(FilterMode::Linear);
+  canvas
 }
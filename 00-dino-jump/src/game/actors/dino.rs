@@ -1,50 +1,343 @@
 use macroquad::prelude::*;
 
+use crate::game::TICK;
+use crate::game::collision::Collider;
+use crate::game::simulation::{Input, Viewport};
+use crate::game::sprites::{Sheet, Sprites};
+use crate::game::themes::Palette;
+use super::{Actor, World};
+use super::power_up::{Effects, PowerUpKind};
+
+pub const STAND_HEIGHT: f32 = 120f32;
+pub const DUCK_HEIGHT: f32 = 60f32;
+pub const WIDTH: f32 = 50f32;
+const DUCK_WIDTH: f32 = 70f32;
+
+// Pixels per second, and pixels per second squared. Holding jump while rising
+// halves gravity, releasing it cuts the remaining upward speed.
+const JUMP_VELOCITY: f32 = 650f32;
+const JUMP_CUT_VELOCITY: f32 = 550f32;
+const GRAVITY: f32 = 2800f32;
+const HELD_GRAVITY: f32 = 1400f32;
+const DIVE_GRAVITY: f32 = 5600f32;
+const LANDING_TICKS: u32 = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JumpState {
+  Grounded,
+  Rising,
+  Falling,
+  /// Back on the ground for a few ticks, jumping again is already allowed.
+  Landing(u32)
+}
+
+/// Timings of a jump held all the way, in seconds.
+pub struct JumpProfile {
+  pub airtime: f32,
+  /// Time spent above the obstacle height the profile was measured for.
+  pub clearance: f32
+}
+
+impl JumpProfile {
+  /// Plays a jump tick by tick, so the profile always matches the actual physics.
+  pub fn measure(obstacle_height: f32) -> Self {
+    let mut dino = Dino::new(&Viewport { width: 0f32, height: 0f32 });
+    let ground = dino.rect.bottom();
+    let held = Input { jump: true, duck: false };
+    let (mut airborne, mut above) = (0, 0);
+    
+    dino.update(&held, true);
+    while matches!(dino.state, JumpState::Rising | JumpState::Falling) {
+      dino.update(&held, false);
+      airborne += 1;
+      if ground - dino.rect.bottom() > obstacle_height {
+        above += 1;
+      }
+    }
+    
+    Self { airtime: airborne as f32 * TICK, clearance: above as f32 * TICK }
+  }
+}
+
 pub struct Dino {
   pub rect: Rect,
-  is_jumping: bool,
-  jumped_at: Option<f32>,
-  velocity: f32
+  ground: f32,
+  state: JumpState,
+  ducking: bool,
+  velocity: f32,
+  pose_ticks: u32,
+  effects: Effects,
+  air_jumped: bool,
+  dead: bool
 }
 
 impl Dino {
-  pub fn new() -> Self {
-    let (width, height) = (50f32, 120f32);
+  pub fn new(viewport: &Viewport) -> Self {
+    let ground = viewport.ground();
     Self {
-      rect: Rect::new(width, (screen_height() - height) * 0.5f32, width, height),
-      is_jumping: false,
-      jumped_at: None,
-      velocity: 0f32
-    }
-  }
-  
-  pub fn update(&mut self, elapsed: f32) {
-    if self.is_jumping == false && is_key_pressed(KeyCode::Space) {
-      self.is_jumping = true;
-      self.velocity = 10.0;
-    }
-
-    if self.is_jumping {
-      self.jumped_at = match self.jumped_at {
-        None =>
-          Some(elapsed),
-        
-        Some(delta_time) =>
-          if delta_time >= 1.0f32 {
-            self.is_jumping = false;
-            self.velocity = 0f32;
-            self.rect.y = (screen_height() - 120f32) * 0.5f32;
-            None
-          } else {
-            self.rect.y -= self.velocity;
-            self.velocity -= 20f32 * elapsed;
-            Some(delta_time + elapsed)
-          }
-      }
+      rect: Rect::new(WIDTH, ground - STAND_HEIGHT, WIDTH, STAND_HEIGHT),
+      ground,
+      state: JumpState::Grounded,
+      ducking: false,
+      velocity: 0f32,
+      pose_ticks: 0,
+      effects: Effects::default(),
+      air_jumped: false,
+      dead: false
+    }
+  }
+  
+  /// Name of the current pose, shared by its sprite clip and its hitboxes.
+  pub fn pose(& self) -> &'static str {
+    if self.dead {
+      return "dead";
     }
+    if self.ducking {
+      return "duck";
+    }
+    
+    match self.state {
+      JumpState::Rising | JumpState::Falling => "jump",
+      JumpState::Grounded | JumpState::Landing(_) => "run"
+    }
+  }
+  
+  /// Off the ground, from the jump until the landing.
+  pub fn is_airborne(& self) -> bool {
+    matches!(self.state, JumpState::Rising | JumpState::Falling)
+  }
+  
+  pub fn effects(& self) -> &Effects {
+    &self.effects
+  }
+  
+  pub fn collect(&mut self, kind: PowerUpKind) {
+    self.effects.apply(kind);
+  }
+  
+  /// The run is over, the dino stays as it is with its `dead` pose.
+  pub fn die(&mut self) {
+    self.dead = true;
+    self.pose_ticks = 0;
+  }
+  
+  /// Plays on the clip of the current pose without moving, for the `dead` one to play out
+  /// once the simulation stopped stepping.
+  pub fn animate(&mut self) {
+    self.pose_ticks += 1;
+  }
+  
+  /// Uses up the shield, if any, returns whether the hit was absorbed.
+  pub fn absorb_hit(&mut self) -> bool {
+    self.effects.consume(PowerUpKind::Shield)
+  }
+  
+  /// Advances the dino by one simulation tick.
+  pub fn update(&mut self, input: &Input, jump_pressed: bool) {
+    let pose = self.pose();
+    self.effects.update();
+    
+    self.state = match self.state {
+      JumpState::Grounded | JumpState::Landing(_) if jump_pressed => {
+        self.velocity = JUMP_VELOCITY;
+        self.air_jumped = false;
+        JumpState::Rising
+      },
+      JumpState::Rising | JumpState::Falling if jump_pressed && !self.air_jumped && self.effects.has(PowerUpKind::DoubleJump) => {
+        self.velocity = JUMP_VELOCITY;
+        self.air_jumped = true;
+        JumpState::Rising
+      },
+      JumpState::Grounded => JumpState::Grounded,
+      JumpState::Landing(ticks) => if ticks > 1 { JumpState::Landing(ticks - 1) } else { JumpState::Grounded },
+      JumpState::Rising | JumpState::Falling => self.fly(input)
+    };
+    
+    let grounded = matches!(self.state, JumpState::Grounded | JumpState::Landing(_));
+    self.set_ducking(grounded && input.duck);
+    
+    self.pose_ticks = if self.pose() == pose { self.pose_ticks + 1 } else { 0 };
+  }
+  
+  // Ducking keeps the feet in place and makes the collider lower but longer.
+  fn set_ducking(&mut self, ducking: bool) {
+    let (width, height) = if ducking { (DUCK_WIDTH, DUCK_HEIGHT) } else { (WIDTH, STAND_HEIGHT) };
+    let bottom = self.rect.bottom();
+    self.rect = Rect::new(self.rect.x, bottom - height, width, height);
+    self.ducking = ducking;
+  }
+  
+  fn fly(&mut self, input: &Input) -> JumpState {
+    let rising = self.state == JumpState::Rising;
+    
+    if rising && !input.jump {
+      self.velocity = self.velocity.min(JUMP_CUT_VELOCITY);
+    }
+    
+    // Holding duck in the air dives back to the ground.
+    let gravity = if input.duck {
+      DIVE_GRAVITY
+    } else if rising && input.jump {
+      HELD_GRAVITY
+    } else {
+      GRAVITY
+    };
+    self.rect.y -= self.velocity * TICK;
+    self.velocity -= gravity * TICK;
+    
+    if self.rect.bottom() >= self.ground {
+      self.rect.y = self.ground - self.rect.h;
+      self.velocity = 0f32;
+      JumpState::Landing(LANDING_TICKS)
+    } else if self.velocity > 0f32 {
+      JumpState::Rising
+    } else {
+      JumpState::Falling
+    }
+  }
+  
+  fn draw_shape(& self, palette: &Palette) {
+    // Squash a little on landing, the collider stays the same.
+    let squash = match self.state {
+      JumpState::Landing(ticks) => ticks as f32 * 2f32,
+      _ => 0f32
+    };
+    draw_rectangle(self.rect.x - squash * 0.5f32, self.rect.y + squash, self.rect.w + squash, self.rect.h - squash, palette.dino)
+  }
+}
+
+impl Actor for Dino {
+  fn update(&mut self, world: &World) {
+    Dino::update(self, &world.input, world.jump_pressed);
+  }
+  
+  fn collider(& self) -> Option<Collider<'static>> {
+    Some((self.pose(), self.pose_ticks, self.rect))
   }
   
-  pub fn draw(& self) {
-    draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, BLACK)
+  fn draw(& self, sprites: &Sprites, palette: &Palette) {
+    if !sprites.draw(Sheet::Dino, self.pose(), self.pose_ticks as f32 * TICK, self.rect, palette.tint) {
+      self.draw_shape(palette);
+    }
+    
+    if self.effects.has(PowerUpKind::Shield) {
+      let center = self.rect.center();
+      draw_circle_lines(center.x, center.y, self.rect.w.max(self.rect.h) * 0.7f32, 3f32, palette.highlight);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Dino, JumpState, DUCK_HEIGHT};
+  use crate::game::actors::power_up::PowerUpKind;
+  use crate::game::simulation::{Input, Viewport};
+
+  const VIEWPORT: Viewport = Viewport { width: 800f32, height: 600f32 };
+
+  // Holds jump for `held_ticks`, returns the apex height and the ticks spent in the air.
+  fn jump(held_ticks: u32) -> (f32, u32) {
+    let mut dino = Dino::new(&VIEWPORT);
+    let ground = dino.rect.bottom();
+    let (mut apex, mut airborne) = (0f32, 0);
+
+    dino.update(&Input { jump: true, duck: false }, true);
+    while matches!(dino.state, JumpState::Rising | JumpState::Falling) {
+      dino.update(&Input { jump: airborne < held_ticks, duck: false }, false);
+      apex = apex.max(ground - dino.rect.bottom());
+      airborne += 1;
+    }
+
+    (apex, airborne)
+  }
+
+  #[test]
+  fn holding_jumps_higher() {
+    let (tap_apex, tap_airtime) = jump(0);
+    let (held_apex, held_airtime) = jump(u32::MAX);
+
+    assert!(tap_apex > 40f32, "tap only reached {}px", tap_apex);
+    assert!(held_apex > 2f32 * tap_apex, "held {}px vs tap {}px", held_apex, tap_apex);
+    assert!(held_airtime > tap_airtime);
+  }
+
+  #[test]
+  fn lands_on_the_ground_line() {
+    let mut dino = Dino::new(&VIEWPORT);
+    let ground = dino.rect.bottom();
+
+    dino.update(&Input { jump: true, duck: false }, true);
+    while dino.state != JumpState::Grounded {
+      dino.update(&Input::default(), false);
+    }
+
+    assert_eq!(dino.rect.bottom(), ground);
+  }
+
+  #[test]
+  fn can_jump_again_while_landing() {
+    let mut dino = Dino::new(&VIEWPORT);
+
+    dino.update(&Input { jump: true, duck: false }, true);
+    while !matches!(dino.state, JumpState::Landing(_)) {
+      dino.update(&Input::default(), false);
+    }
+    dino.update(&Input { jump: true, duck: false }, true);
+
+    assert_eq!(dino.state, JumpState::Rising);
+  }
+
+  #[test]
+  fn ducking_lowers_the_collider() {
+    let mut dino = Dino::new(&VIEWPORT);
+    let ground = dino.rect.bottom();
+
+    dino.update(&Input { jump: false, duck: true }, false);
+    assert_eq!(dino.rect.h, DUCK_HEIGHT);
+    assert_eq!(dino.rect.bottom(), ground);
+
+    dino.update(&Input::default(), false);
+    assert!(!dino.ducking);
+    assert_eq!(dino.rect.bottom(), ground);
+  }
+
+  #[test]
+  fn diving_lands_sooner() {
+    let airtime = |duck: bool| {
+      let mut dino = Dino::new(&VIEWPORT);
+      let mut ticks = 0;
+      dino.update(&Input { jump: true, duck: false }, true);
+      while matches!(dino.state, JumpState::Rising | JumpState::Falling) {
+        dino.update(&Input { jump: true, duck: duck && ticks > 20 }, false);
+        ticks += 1;
+      }
+      ticks
+    };
+
+    assert!(airtime(true) < airtime(false));
+  }
+
+  #[test]
+  fn double_jump_needs_the_power_up() {
+    // Presses jump, then again `delay` ticks later, returns the velocity right after.
+    let jump_again = |dino: &mut Dino, delay: u32| {
+      dino.update(&Input { jump: true, duck: false }, true);
+      for _ in 0..delay {
+        dino.update(&Input::default(), false);
+      }
+      dino.update(&Input { jump: true, duck: false }, true);
+      dino.velocity
+    };
+
+    assert!(jump_again(&mut Dino::new(&VIEWPORT), 30) < 0f32);
+
+    let mut dino = Dino::new(&VIEWPORT);
+    dino.collect(PowerUpKind::DoubleJump);
+    assert_eq!(jump_again(&mut dino, 30), super::JUMP_VELOCITY);
+
+    // Only once per jump.
+    dino.update(&Input::default(), false);
+    dino.update(&Input { jump: true, duck: false }, true);
+    assert!(dino.velocity < super::JUMP_VELOCITY);
   }
 }
@@ -1,3 +1,46 @@
+use crate::game::collision::Collider;
+use crate::game::simulation::Input;
+use crate::game::sprites::Sprites;
+use crate::game::themes::Palette;
+
 pub mod dino;
 pub mod obstacle;
+pub mod pool;
+pub mod power_up;
+pub mod scene;
 pub mod spawner;
+
+/// What every actor gets to see on a tick.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct World {
+  pub input: Input,
+  /// Jump went down on this tick.
+  pub jump_pressed: bool,
+  /// Scroll speed, in pixels per second.
+  pub speed: f32
+}
+
+/// Anything that lives in the playfield: the dino, obstacles, power-ups, scenery...
+///
+/// The simulation keeps its own actors in fields and pools rather than behind boxes, stepped
+/// in a fixed order without allocating, with collisions only between the dino and the others.
+/// Obstacles and power-ups are updated through this trait, the dino with its own `Dino::update`
+/// since what it does decides the rest of the tick. All of them are collided and drawn through it.
+/// A [`scene::Scene`] boxes the actors living beside the simulation, like the ghost, as its
+/// props add them for each run.
+pub trait Actor {
+  /// Advances the actor by one simulation tick.
+  fn update(&mut self, world: &World);
+
+  /// What collisions are checked against, scenery has none.
+  fn collider(& self) -> Option<Collider<'static>> {
+    None
+  }
+
+  fn draw(& self, sprites: &Sprites, palette: &Palette);
+
+  /// Actors are drawn from the lowest to the highest, in the order they come in when equal.
+  fn z_order(& self) -> i32 {
+    0
+  }
+}
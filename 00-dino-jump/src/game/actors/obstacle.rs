@@ -1,28 +1,101 @@
 use macroquad::prelude::*;
 
+use crate::game::TICK;
+use crate::game::collision::Collider;
+use crate::game::simulation::Viewport;
+use crate::game::sprites::{Sheet, Sprites};
+use crate::game::themes::Palette;
+use super::{Actor, World};
+
+/// Unique within a run, in spawn order.
+pub type ObstacleId = u32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObstacleKind {
+  Cactus,
+  Bird
+}
+
+impl ObstacleKind {
+  pub fn size(& self) -> (f32, f32) {
+    match self {
+      ObstacleKind::Cactus => (40f32, 40f32),
+      ObstacleKind::Bird => (50f32, 30f32)
+    }
+  }
+}
+
 #[derive(Clone, Copy)]
 pub struct Obstacle {
-  pub rect: Rect
+  pub rect: Rect,
+  id: ObstacleId,
+  kind: ObstacleKind,
+  passed: bool,
+  age: u32
 }
 
 impl Obstacle {
-  pub fn new() -> Self {
+  /// Spawns an obstacle right of the screen, `elevation` pixels above the ground.
+  pub fn new(viewport: &Viewport, id: ObstacleId, kind: ObstacleKind, elevation: f32) -> Self {
+    let (width, height) = kind.size();
     Self {
-      rect: Rect::new(screen_width() + 100f32, (screen_height() + 40f32) * 0.5f32, 40f32, 40f32)
+      rect: Rect::new(viewport.width + 100f32, viewport.ground() - elevation - height, width, height),
+      id,
+      kind,
+      passed: false,
+      age: 0
     }
   }
   
-  pub fn update(&mut self, elapsed: f32) {
-    self.rect.x -= 640f32 * elapsed;
+  pub fn id(& self) -> ObstacleId {
+    self.id
   }
   
-  pub fn draw(& self) {
-    draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, RED)
+  pub fn kind(& self) -> ObstacleKind {
+    self.kind
+  }
+  
+  /// Name of the obstacle's pose, shared by its sprite clip and its hitboxes.
+  pub fn pose(& self) -> &'static str {
+    match self.kind {
+      ObstacleKind::Cactus => "cactus",
+      ObstacleKind::Bird => "bird"
+    }
+  }
+  
+  /// Returns true only on the tick the obstacle gets fully behind `x`.
+  pub fn pass(&mut self, x: f32) -> bool {
+    let just_passed = !self.passed && self.rect.right() < x;
+    self.passed |= just_passed;
+    just_passed
+  }
+}
+
+impl Actor for Obstacle {
+  fn update(&mut self, world: &World) {
+    self.rect.x -= world.speed * TICK;
+    self.age += 1;
+  }
+  
+  fn collider(& self) -> Option<Collider<'static>> {
+    Some((self.pose(), self.age, self.rect))
+  }
+  
+  fn draw(& self, sprites: &Sprites, palette: &Palette) {
+    if sprites.draw(Sheet::Obstacles, self.pose(), self.age as f32 * TICK, self.rect, palette.tint) {
+      return;
+    }
+    
+    let color = match self.kind {
+      ObstacleKind::Cactus => palette.cactus,
+      ObstacleKind::Bird => palette.bird
+    };
+    draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, color)
   }
 }
 
 impl std::fmt::Display for Obstacle {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{} {}", self.rect.x, self.rect.y)
+    write!(f, "#{} {:?} {} {}", self.id, self.kind, self.rect.x, self.rect.y)
   }
 }
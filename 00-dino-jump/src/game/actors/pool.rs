@@ -0,0 +1,76 @@
+use std::ops::{Deref, DerefMut};
+
+/// A list of actors sized once, for as many as can be around at a time. Its buffer is
+/// allocated once: slots freed by `retain` or `clear` are recycled by the next `push`.
+/// Actors keep the order they were pushed in, oldest first.
+pub struct Pool<T> {
+  items: Vec<T>
+}
+
+impl<T> Pool<T> {
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self { items: Vec::with_capacity(capacity) }
+  }
+
+  /// Pools are sized so that they never fill up. Should one still do, it grows rather than
+  /// losing an actor, and says so: the sizing is wrong.
+  pub fn push(&mut self, item: T) {
+    if self.items.len() == self.capacity() {
+      eprintln!("Pool of {} is full, growing it", self.capacity());
+    }
+    self.items.push(item);
+  }
+
+  pub fn capacity(& self) -> usize {
+    self.items.capacity()
+  }
+
+  pub fn retain(&mut self, keep: impl FnMut(&T) -> bool) {
+    self.items.retain(keep);
+  }
+
+  pub fn clear(&mut self) {
+    self.items.clear();
+  }
+}
+
+impl<T> Deref for Pool<T> {
+  type Target = [T];
+
+  fn deref(& self) -> &[T] {
+    &self.items
+  }
+}
+
+impl<T> DerefMut for Pool<T> {
+  fn deref_mut(&mut self) -> &mut [T] {
+    &mut self.items
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Pool;
+
+  #[test]
+  fn slots_are_recycled_in_order() {
+    let mut pool = Pool::with_capacity(3);
+    pool.push(1);
+    pool.push(2);
+    pool.push(3);
+    pool.retain(|&item| item != 2);
+    pool.push(4);
+
+    assert_eq!(&pool[..], &[1, 3, 4]);
+  }
+
+  #[test]
+  fn grows_when_full() {
+    let mut pool = Pool::with_capacity(1);
+    pool.push(1);
+    pool.push(2);
+
+    assert_eq!(&pool[..], &[1, 2]);
+    assert!(pool.capacity() >= 2);
+  }
+}
@@ -0,0 +1,179 @@
+use macroquad::prelude::*;
+
+use crate::game::TICK;
+use crate::game::TICKS_PER_SECOND;
+use crate::game::collision::Collider;
+use crate::game::simulation::Viewport;
+use crate::game::sprites::{Sheet, Sprites};
+use crate::game::themes::Palette;
+use super::{Actor, World};
+
+const SIZE: f32 = 30f32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerUpKind {
+  /// Survives the next hit.
+  Shield,
+  /// Slows the world down, not the dino.
+  SlowMotion,
+  /// One more jump while airborne.
+  DoubleJump
+}
+
+impl PowerUpKind {
+  pub const ALL: [PowerUpKind; 3] = [PowerUpKind::Shield, PowerUpKind::SlowMotion, PowerUpKind::DoubleJump];
+
+  /// How long the effect lasts once collected, in ticks.
+  pub fn duration(& self) -> u32 {
+    let seconds = match self {
+      PowerUpKind::Shield => 10,
+      PowerUpKind::SlowMotion => 5,
+      PowerUpKind::DoubleJump => 10
+    };
+    seconds * TICKS_PER_SECOND
+  }
+
+  pub fn name(& self) -> &'static str {
+    match self {
+      PowerUpKind::Shield => "Shield",
+      PowerUpKind::SlowMotion => "Slow motion",
+      PowerUpKind::DoubleJump => "Double jump"
+    }
+  }
+}
+
+/// A collectible floating along with the obstacles.
+pub struct PowerUp {
+  pub rect: Rect,
+  kind: PowerUpKind,
+  age: u32
+}
+
+impl PowerUp {
+  /// Spawns a power-up `distance` pixels right of where obstacles spawn, `elevation` pixels above the ground.
+  pub fn new(viewport: &Viewport, kind: PowerUpKind, distance: f32, elevation: f32) -> Self {
+    Self {
+      rect: Rect::new(viewport.width + 100f32 + distance, viewport.ground() - elevation - SIZE, SIZE, SIZE),
+      kind,
+      age: 0
+    }
+  }
+
+  pub fn kind(& self) -> PowerUpKind {
+    self.kind
+  }
+
+  /// Name of the power-up's pose, shared by its sprite clip and its hitboxes.
+  pub fn pose(& self) -> &'static str {
+    match self.kind {
+      PowerUpKind::Shield => "shield",
+      PowerUpKind::SlowMotion => "slow-motion",
+      PowerUpKind::DoubleJump => "double-jump"
+    }
+  }
+}
+
+impl Actor for PowerUp {
+  fn update(&mut self, world: &World) {
+    self.rect.x -= world.speed * TICK;
+    self.age += 1;
+  }
+
+  fn collider(& self) -> Option<Collider<'static>> {
+    Some((self.pose(), self.age, self.rect))
+  }
+
+  /// Power-ups come from the obstacles sheet.
+  fn draw(& self, sprites: &Sprites, palette: &Palette) {
+    if sprites.draw(Sheet::Obstacles, self.pose(), self.age as f32 * TICK, self.rect, palette.tint) {
+      return;
+    }
+
+    let center = self.rect.center();
+    let letter = match self.kind {
+      PowerUpKind::Shield => "S",
+      PowerUpKind::SlowMotion => "T",
+      PowerUpKind::DoubleJump => "J"
+    };
+
+    draw_circle(center.x, center.y, SIZE * 0.5f32, palette.highlight);
+    draw_text(letter, center.x - 7f32, center.y + 8f32, 28f32, palette.background);
+  }
+}
+
+/// A collected power-up, running out tick by tick.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Effect {
+  pub kind: PowerUpKind,
+  pub ticks_left: u32
+}
+
+/// Timed effects of the power-ups the dino collected, at most one of each kind.
+pub struct Effects(Vec<Effect>);
+
+impl Default for Effects {
+  fn default() -> Self {
+    Self(Vec::with_capacity(PowerUpKind::ALL.len()))
+  }
+}
+
+impl Effects {
+  /// Starts an effect, or restarts its timer when it's already running.
+  pub fn apply(&mut self, kind: PowerUpKind) {
+    self.0.retain(|effect| effect.kind != kind);
+    self.0.push(Effect { kind, ticks_left: kind.duration() });
+  }
+
+  pub fn has(& self, kind: PowerUpKind) -> bool {
+    self.0.iter().any(|effect| effect.kind == kind)
+  }
+
+  /// Ends an effect early, returns whether it was running.
+  pub fn consume(&mut self, kind: PowerUpKind) -> bool {
+    let running = self.has(kind);
+    self.0.retain(|effect| effect.kind != kind);
+    running
+  }
+
+  pub fn update(&mut self) {
+    for effect in self.0.iter_mut() {
+      effect.ticks_left = effect.ticks_left.saturating_sub(1);
+    }
+    self.0.retain(|effect| effect.ticks_left > 0);
+  }
+
+  pub fn iter(& self) -> impl Iterator<Item = &Effect> {
+    self.0.iter()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Effects, PowerUpKind};
+
+  #[test]
+  fn effects_run_out() {
+    let mut effects = Effects::default();
+    effects.apply(PowerUpKind::SlowMotion);
+
+    for _ in 1..PowerUpKind::SlowMotion.duration() {
+      effects.update();
+    }
+    assert!(effects.has(PowerUpKind::SlowMotion));
+
+    effects.update();
+    assert!(!effects.has(PowerUpKind::SlowMotion));
+  }
+
+  #[test]
+  fn collecting_again_restarts_the_timer() {
+    let mut effects = Effects::default();
+    effects.apply(PowerUpKind::Shield);
+    effects.update();
+    effects.apply(PowerUpKind::Shield);
+
+    assert_eq!(effects.iter().map(|effect| effect.ticks_left).collect::<Vec<u32>>(), vec!(PowerUpKind::Shield.duration()));
+    assert!(effects.consume(PowerUpKind::Shield));
+    assert!(!effects.consume(PowerUpKind::Shield));
+  }
+}
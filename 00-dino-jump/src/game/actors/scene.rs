@@ -0,0 +1,147 @@
+use crate::game::ghost;
+use crate::game::settings::Settings;
+use crate::game::simulation::Simulation;
+use crate::game::sprites::Sprites;
+use crate::game::themes::Palette;
+use super::{Actor, World};
+
+/// What a scene is filled for: the run about to start on the first lane.
+pub struct Setup<'a> {
+  pub simulation: &'a Simulation,
+  pub settings: &'a Settings,
+  /// A replay or a race, which already show another run next to the player's.
+  pub rival: bool
+}
+
+/// Adds its actors to the scene of a run, or none when the setup doesn't call for them.
+pub type Prop = fn(&Setup, &mut Scene);
+
+/// What every run's scene is filled with. A new prop only needs adding here.
+const PROPS: [Prop; 1] = [ghost::populate];
+
+/// Owns actors of any kind, for the ones living next to the simulation rather than in it,
+/// like the ghost. They share its ticks, but never its collisions.
+pub struct Scene {
+  actors: Vec<Box<dyn Actor>>,
+  props: Vec<Prop>,
+  score_to_beat: Option<u32>
+}
+
+impl Default for Scene {
+  fn default() -> Self {
+    Self { actors: vec!(), props: PROPS.to_vec(), score_to_beat: None }
+  }
+}
+
+impl Scene {
+  pub fn add(&mut self, actor: Box<dyn Actor>) {
+    self.actors.push(actor);
+  }
+
+  /// Empties the scene, then lets every prop add its actors for the run about to start.
+  pub fn fill(&mut self, setup: &Setup) {
+    self.actors.clear();
+    self.score_to_beat = None;
+    for prop in self.props.clone() {
+      prop(setup, self);
+    }
+  }
+
+  /// Shown next to the score, e.g. that of the ghost's run.
+  pub fn score_to_beat(& self) -> Option<u32> {
+    self.score_to_beat
+  }
+
+  pub fn set_score_to_beat(&mut self, score: u32) {
+    self.score_to_beat = Some(score);
+  }
+
+  pub fn update(&mut self, world: &World) {
+    for actor in self.actors.iter_mut() {
+      actor.update(world);
+    }
+  }
+
+  pub fn actors(& self) -> impl Iterator<Item = &dyn Actor> {
+    self.actors.iter().map(|actor| actor.as_ref())
+  }
+}
+
+/// Draws actors from any number of sources, by z-order.
+pub fn draw<'a>(actors: impl Iterator<Item = &'a dyn Actor>, sprites: &Sprites, palette: &Palette) {
+  let mut actors: Vec<&dyn Actor> = actors.collect();
+  actors.sort_by_key(|actor| actor.z_order());
+
+  for actor in actors {
+    actor.draw(sprites, palette);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::RefCell;
+  use std::rc::Rc;
+
+  use super::{Scene, Setup};
+  use crate::game::CANVAS;
+  use crate::game::actors::{Actor, World};
+  use crate::game::settings::Settings;
+  use crate::game::simulation::Simulation;
+  use crate::game::sprites::Sprites;
+  use crate::game::themes::{Palette, ThemeCycle, Themes};
+
+  // Writes its name down whenever it's drawn.
+  struct Marker {
+    name: &'static str,
+    z_order: i32,
+    ticks: u32,
+    drawn: Rc<RefCell<Vec<&'static str>>>
+  }
+
+  impl Actor for Marker {
+    fn update(&mut self, _world: &World) {
+      self.ticks += 1;
+    }
+
+    fn draw(& self, _sprites: &Sprites, _palette: &Palette) {
+      self.drawn.borrow_mut().push(self.name);
+    }
+
+    fn z_order(& self) -> i32 {
+      self.z_order
+    }
+  }
+
+  #[test]
+  fn draws_by_z_order_then_insertion() {
+    let drawn = Rc::new(RefCell::new(vec!()));
+    let mut scene = Scene { props: vec!(), ..Scene::default() };
+    for (name, z_order) in [("front", 1), ("back", -1), ("middle", 0), ("also middle", 0)] {
+      scene.add(Box::new(Marker { name, z_order, ticks: 0, drawn: drawn.clone() }));
+    }
+
+    scene.update(&World::default());
+    super::draw(scene.actors(), &Sprites::default(), &ThemeCycle::new(Themes::load()).palette());
+
+    assert_eq!(*drawn.borrow(), vec!("back", "middle", "also middle", "front"));
+  }
+
+  #[test]
+  fn props_fill_every_run() {
+    let mut scene = Scene { props: vec!(|setup, scene| {
+      if !setup.rival {
+        scene.set_score_to_beat(42);
+        scene.add(Box::new(Marker { name: "prop", z_order: 0, ticks: 0, drawn: Default::default() }));
+      }
+    }), ..Scene::default() };
+    let simulation = Simulation::new(CANVAS, 1);
+    let settings = Settings::default();
+
+    scene.fill(&Setup { simulation: &simulation, settings: &settings, rival: false });
+    scene.fill(&Setup { simulation: &simulation, settings: &settings, rival: false });
+    assert_eq!((scene.actors().count(), scene.score_to_beat()), (1, Some(42)));
+
+    scene.fill(&Setup { simulation: &simulation, settings: &settings, rival: true });
+    assert_eq!((scene.actors().count(), scene.score_to_beat()), (0, None));
+  }
+}
@@ -1,82 +1,342 @@
+use crate::game::TICK;
+use crate::game::collision::{self, Hitboxes};
+use crate::game::difficulty::Level;
+use crate::game::events::Event;
+use crate::game::rng::Rng;
+use crate::game::simulation::{Viewport, SLOW_MOTION_SCALE};
+use super::{Actor, World};
+use super::dino::{self, Dino, JumpProfile};
+use super::obstacle::{Obstacle, ObstacleId, ObstacleKind};
+use super::pool::Pool;
+use super::power_up::{PowerUp, PowerUpKind};
 
-use std::vec;
-use macroquad::prelude::*;
-use macroquad::rand::gen_range;
+// Between the leading edges of two cacti of a cluster, in pixels.
+const CLUSTER_SPACING: f32 = 64f32;
+const MAX_CLUSTER: u32 = 3;
+// Gaps get up to this much longer than the minimum, at random.
+const GAP_VARIATION: f32 = 0.6f32;
+// Speed keeps increasing between the moment a gap is chosen and the moment it's jumped.
+const FAIRNESS_MARGIN: f32 = 1.2f32;
 
-use super::dino::Dino;
-use super::obstacle::Obstacle;
+// Birds fly low enough to jump over, at head height to duck under, or high enough to ignore.
+const BIRD_ELEVATIONS: [f32; 3] = [10f32, 75f32, 130f32];
+
+// Power-ups show up every so often, in the middle of a gap. The highest ones need a jump.
+const POWER_UP_INTERVAL: (u32, u32) = (4_000, 8_000);
+const POWER_UP_ELEVATIONS: [f32; 3] = [0f32, 60f32, 140f32];
+// Actors spawn this far right of the playfield, and leave it once fully past its left edge.
+// Obstacles are never closer than `CLUSTER_SPACING`, nor power-ups than the shortest interval.
+const SPAWN_MARGIN: f32 = 100f32;
+const MAX_WIDTH: f32 = 100f32;
+
+// Power-ups draw from their own stream, so they don't change which obstacles a seed gives.
+const POWER_UP_STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
 
 pub struct Spawner {
-  obstacles: Vec<Obstacle>,
-  idle_for: f32
+  viewport: Viewport,
+  obstacles: Pool<Obstacle>,
+  next_id: ObstacleId,
+  jump: JumpProfile,
+  /// Distance the world still has to scroll before the next spawn.
+  until_next: f32,
+  cluster: u32,
+  rng: Rng,
+  power_ups: Pool<PowerUp>,
+  /// Distance the world still has to scroll before a power-up can show up.
+  until_power_up: f32,
+  power_up_rng: Rng
 }
 
 impl Spawner {
-  pub fn new() -> Self {
+  pub fn new(viewport: &Viewport, seed: u64) -> Self {
     Self {
-      obstacles: vec!(),
-      idle_for: get_frame_time()
+      viewport: *viewport,
+      obstacles: Pool::with_capacity(capacity(viewport, CLUSTER_SPACING)),
+      next_id: 0,
+      jump: JumpProfile::measure(ObstacleKind::Cactus.size().1),
+      until_next: viewport.width,
+      cluster: 0,
+      rng: Rng::new(seed),
+      // Power-ups show up anywhere in their gap, which can bring two of them closer than the interval.
+      power_ups: Pool::with_capacity(capacity(viewport, POWER_UP_INTERVAL.0 as f32 * 0.5f32)),
+      until_power_up: POWER_UP_INTERVAL.0 as f32,
+      power_up_rng: Rng::new(seed ^ POWER_UP_STREAM)
     }
   }
   
-  pub fn reset(&mut self) {
-    self.obstacles =  vec!();
-    self.idle_for = get_frame_time();
+  pub fn reset(&mut self, seed: u64) {
+    self.obstacles.clear();
+    self.next_id = 0;
+    self.until_next = self.viewport.width;
+    self.cluster = 0;
+    self.rng = Rng::new(seed);
+    self.power_ups.clear();
+    self.until_power_up = POWER_UP_INTERVAL.0 as f32;
+    self.power_up_rng = Rng::new(seed ^ POWER_UP_STREAM);
   }
   
-  pub fn update(&mut self, elapsed: f32) -> bool {
-    let all_obstacles = self.obstacles.to_owned();
-    
-    self.obstacles.retain(|obstacle| obstacle.rect.x >= 0f32);
-    let remaining_obstacles = self.obstacles.to_owned();
-    let avoided_obstacles: Vec<&Obstacle> =
-    all_obstacles
-    .iter()
-    .filter(|obstacle| remaining_obstacles.iter().all(|remaining| remaining.rect.x != obstacle.rect.x))
-    .collect();
-    
-    avoided_obstacles.iter().for_each(|obs| println!("Avoided: {}", obs));
+  /// Advances every obstacle and power-up by one simulation tick, reporting spawns and obstacles the dino got past.
+  /// The world scrolls at `speed`, slowed down or not, while spawns are planned for `level` at either speed:
+  /// slow motion can start or wear off before what it sees gets jumped.
+  pub fn update(&mut self, level: &Level, speed: f32, dino: &Dino, events: &mut Vec<Event>) {
+    self.obstacles.retain(|obstacle| obstacle.rect.right() >= 0f32);
+    self.power_ups.retain(|power_up| power_up.rect.right() >= 0f32);
     
-    self.obstacles.iter_mut().for_each(|obstacle| obstacle.update(elapsed));
-    
-    // Generate a second obstacle.
-    if self.idle_for >= 0.1f32 && self.idle_for < 0.11f32 {
-      if gen_range(0, 10) * 10 < 20 {
-        self.generate();
+    let world = World { speed, ..World::default() };
+    for obstacle in self.obstacles.iter_mut() {
+      obstacle.update(&world);
+      if obstacle.pass(dino.rect.left()) {
+        events.push(Event::ObstaclePassed(obstacle.id()));
       }
     }
+    for power_up in self.power_ups.iter_mut() {
+      power_up.update(&world);
+    }
     
-    // Generate a third obstacle.
-    if self.idle_for >= 0.2f32 && self.idle_for < 0.21f32 {
-      if gen_range(0, 10) * 10 < 20 {
-        self.generate();
+    self.until_next -= speed * TICK;
+    self.until_power_up -= speed * TICK;
+    if self.until_next <= 0f32 {
+      let kind = if self.cluster == 0 && self.rng.gen_range(0, 4) == 0 { ObstacleKind::Bird } else { ObstacleKind::Cactus };
+      let overshoot = self.until_next;
+      events.push(Event::ObstacleSpawned(self.generate(kind)));
+      self.plan_next(kind, level);
+      
+      if self.cluster == 0 && self.until_power_up <= 0f32 {
+        self.generate_power_up(kind, overshoot);
       }
     }
+  }
+  
+  pub fn generate(&mut self, kind: ObstacleKind) -> ObstacleId {
+    let elevation = match kind {
+      ObstacleKind::Cactus => 0f32,
+      ObstacleKind::Bird => BIRD_ELEVATIONS[self.rng.gen_range(0, BIRD_ELEVATIONS.len() as u32) as usize]
+    };
     
-    if self.idle_for >= 2f32 {
-      self.generate();
-      self.idle_for = 0f32;
-    } else {
-      self.idle_for += elapsed;
+    // Spawning happens on whole ticks, shift back by the overshoot to keep spacings exact.
+    let id = self.next_id;
+    let mut obstacle = Obstacle::new(&self.viewport, id, kind, elevation);
+    obstacle.rect.x += self.until_next.min(0f32);
+    self.obstacles.push(obstacle);
+    self.next_id += 1;
+    id
+  }
+  
+  // Right between the obstacle just spawned and the next one, `overshoot` being how far past
+  // its spawn point the obstacle was generated.
+  fn generate_power_up(&mut self, after: ObstacleKind, overshoot: f32) {
+    let rng = &mut self.power_up_rng;
+    let kind = PowerUpKind::ALL[rng.gen_range(0, PowerUpKind::ALL.len() as u32) as usize];
+    let elevation = POWER_UP_ELEVATIONS[rng.gen_range(0, POWER_UP_ELEVATIONS.len() as u32) as usize];
+    
+    let mut power_up = PowerUp::new(&self.viewport, kind, 0f32, elevation);
+    let gap_start = after.size().0 + overshoot;
+    power_up.rect.x += gap_start + (self.until_next - gap_start - power_up.rect.w) * 0.5f32;
+    self.power_ups.push(power_up);
+    self.until_power_up = rng.gen_range(POWER_UP_INTERVAL.0, POWER_UP_INTERVAL.1) as f32;
+  }
+  
+  // Cacti come in clusters, as long as a single jump clears them all, even in slow motion where
+  // a jump covers less ground. Ducking under a bird leaves no time to jump right after, and the
+  // gap after a cluster is always long enough to land and jump again, even at full speed.
+  fn plan_next(&mut self, kind: ObstacleKind, level: &Level) {
+    self.cluster += 1;
+    
+    if kind == ObstacleKind::Cactus && self.can_extend_cluster(level.speed * SLOW_MOTION_SCALE) && self.rng.gen_float() < level.cluster_probability {
+      self.until_next += CLUSTER_SPACING;
+      return;
     }
     
-    avoided_obstacles.len() == 1
+    let gap = level.min_gap * (1f32 + GAP_VARIATION * self.rng.gen_float());
+    let jumpable_gap = level.speed * self.jump.airtime * FAIRNESS_MARGIN;
+    self.until_next += kind.size().0 + gap.max(jumpable_gap);
+    self.cluster = 0;
   }
   
-  pub fn generate(&mut self) {
-    self.obstacles.push(Obstacle::new());
+  fn can_extend_cluster(& self, speed: f32) -> bool {
+    let span = self.cluster as f32 * CLUSTER_SPACING + ObstacleKind::Cactus.size().0;
+    self.cluster < MAX_CLUSTER && span + dino::WIDTH <= speed * self.jump.clearance
   }
   
-  pub fn any_child_collided(& self, dino: &Dino) -> bool {
-    self.obstacles.iter().any(|obstacle: &Obstacle|
-      match obstacle.rect.intersect(dino.rect) {
-        Some(_intersection) => true,
-        _ => false
+  /// Lines up as many cacti as fit, up to `count`, across the right three quarters of the playfield.
+  /// Meant for profiling, with the world standing still.
+  pub fn fill(&mut self, count: usize) -> usize {
+    self.obstacles.clear();
+    let count = count.min(self.obstacles.capacity());
+    for index in 0..count {
+      self.generate(ObstacleKind::Cactus);
+      self.obstacles[index].rect.x = self.viewport.width * (0.25f32 + 0.75f32 * index as f32 / count as f32);
+    }
+    count
+  }
+  
+  pub fn obstacles(& self) -> &[Obstacle] {
+    &self.obstacles
+  }
+  
+  pub fn power_ups(& self) -> &[PowerUp] {
+    &self.power_ups
+  }
+  
+  /// Reports every obstacle touching the dino, and picks up the power-ups it touches.
+  pub fn collide(&mut self, dino: &Dino, hitboxes: &Hitboxes, events: &mut Vec<Event>) {
+    let Some(dino) = dino.collider() else { return };
+    for obstacle in self.obstacles.iter() {
+      if obstacle.collider().is_some_and(|obstacle| collision::collide(hitboxes, dino, obstacle)) {
+        events.push(Event::Collision(obstacle.id()));
+      }
+    }
+    
+    self.power_ups.retain(|power_up| {
+      let collected = power_up.collider().is_some_and(|power_up| collision::collide(hitboxes, dino, power_up));
+      if collected {
+        events.push(Event::PowerUpCollected(power_up.kind()));
       }
-    )
+      !collected
+    });
   }
   
-  pub fn draw_obstacles(& self) {
-    self.obstacles.iter().for_each(|obstacle| obstacle.draw());
+  /// Removes an obstacle the dino smashed through.
+  pub fn destroy(&mut self, id: ObstacleId) {
+    self.obstacles.retain(|obstacle| obstacle.id() != id);
+  }
+}
+
+// How many actors spaced at least `spacing` apart fit between their spawn point and their exit.
+fn capacity(viewport: &Viewport, spacing: f32) -> usize {
+  ((viewport.width + SPAWN_MARGIN + MAX_WIDTH) / spacing).ceil() as usize + 1
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Spawner;
+  use crate::game::actors::dino::{self, Dino, JumpProfile};
+  use crate::game::actors::obstacle::{Obstacle, ObstacleKind};
+  use crate::game::collision::Hitboxes;
+  use crate::game::difficulty::Level;
+  use crate::game::events::Event;
+  use crate::game::simulation::{Input, Viewport, SLOW_MOTION_SCALE};
+
+  const VIEWPORT: Viewport = Viewport { width: 800f32, height: 600f32 };
+
+  // A bird at head height, right on top of the dino.
+  fn spawner_with_bird() -> Spawner {
+    let mut spawner = Spawner::new(&VIEWPORT, 0);
+    let mut bird = Obstacle::new(&VIEWPORT, 7, ObstacleKind::Bird, super::BIRD_ELEVATIONS[1]);
+    bird.rect.x = 60f32;
+    spawner.obstacles.push(bird);
+    spawner
+  }
+
+  // Widths, leading edges and scrolling speeds of every obstacle spawned in `seconds`, oldest first.
+  // With `slow_motion`, the world scrolls slowed down every other 3 seconds.
+  fn spawn_positions(level: &Level, seconds: u32, slow_motion: bool) -> Vec<(f32, f32, f32)> {
+    let mut spawner = Spawner::new(&VIEWPORT, 3);
+    let dino = Dino::new(&VIEWPORT);
+    let mut scrolled = 0f32;
+    let mut positions = vec!();
+
+    for tick in 0..seconds * crate::game::TICKS_PER_SECOND {
+      let slowed = slow_motion && (tick / (3 * crate::game::TICKS_PER_SECOND)) % 2 == 1;
+      let speed = if slowed { level.speed * SLOW_MOTION_SCALE } else { level.speed };
+      spawner.obstacles.clear();
+      spawner.update(level, speed, &dino, &mut vec!());
+      scrolled += speed * crate::game::TICK;
+      positions.extend(spawner.obstacles.iter().map(|obstacle| (obstacle.rect.w, obstacle.rect.x + scrolled, speed)));
+    }
+
+    positions
+  }
+
+  #[test]
+  fn standing_under_a_bird_collides() {
+    let mut spawner = spawner_with_bird();
+    let dino = Dino::new(&VIEWPORT);
+    let mut events = vec!();
+
+    spawner.collide(&dino, Hitboxes::builtin(), &mut events);
+
+    assert_eq!(events, vec!(Event::Collision(7)));
+  }
+
+  #[test]
+  fn ducking_under_a_bird_is_safe() {
+    let mut spawner = spawner_with_bird();
+    let mut dino = Dino::new(&VIEWPORT);
+    let mut events = vec!();
+    dino.update(&Input { duck: true, ..Input::default() }, false);
+
+    spawner.collide(&dino, Hitboxes::builtin(), &mut events);
+
+    assert!(events.is_empty());
+  }
+
+  #[test]
+  fn every_obstacle_of_a_cluster_is_passed_once() {
+    let mut spawner = Spawner::new(&VIEWPORT, 0);
+    let dino = Dino::new(&VIEWPORT);
+    let level = Level { speed: 600f32, min_gap: 10_000f32, cluster_probability: 0f32 };
+    let mut events = vec!();
+    for id in 0..3 {
+      spawner.generate(ObstacleKind::Cactus);
+      spawner.obstacles[id].rect.x = dino.rect.left() - 30f32 + 5f32 * id as f32;
+    }
+
+    for _ in 0..10 {
+      spawner.update(&level, level.speed, &dino, &mut events);
+    }
+
+    assert_eq!(events, vec!(Event::ObstaclePassed(0), Event::ObstaclePassed(1), Event::ObstaclePassed(2)));
+  }
+
+  #[test]
+  fn gaps_stay_jumpable() {
+    // Nothing but the fairness rules keeps this level playable. Gaps planned in slow motion
+    // still get jumped at full speed once it wears off, clusters at whatever speed they scroll.
+    for (speed, slow_motion) in [300f32, 600f32, 1200f32].into_iter().flat_map(|speed| [(speed, false), (speed, true)]) {
+      let level = Level { speed, min_gap: 0f32, cluster_probability: 1f32 };
+      let jump = JumpProfile::measure(ObstacleKind::Cactus.size().1);
+      let positions = spawn_positions(&level, 30, slow_motion);
+      assert!(positions.len() > 10);
+
+      let mut cluster_start = positions[0];
+      let mut cluster_speed = cluster_start.2;
+      for pair in positions.windows(2) {
+        let ((width, x, _), (_, next_x, next_speed)) = (pair[0], pair[1]);
+        let spacing = next_x - x;
+
+        if (spacing - super::CLUSTER_SPACING).abs() < 0.01f32 {
+          assert_eq!(width, ObstacleKind::Cactus.size().0);
+          cluster_speed = cluster_speed.min(next_speed);
+          let span = next_x + ObstacleKind::Cactus.size().0 - cluster_start.1;
+          assert!(span + dino::WIDTH <= cluster_speed * jump.clearance, "unjumpable cluster of {}px at {}px/s", span, cluster_speed);
+        } else {
+          let gap = spacing - width;
+          assert!(gap >= speed * jump.airtime, "gap of {}px at {}px/s", gap, speed);
+          cluster_start = pair[1];
+          cluster_speed = next_speed;
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn power_ups_show_up_clear_of_obstacles() {
+    let mut spawner = Spawner::new(&VIEWPORT, 5);
+    let dino = Dino::new(&VIEWPORT);
+    let level = Level { speed: 600f32, min_gap: 600f32, cluster_probability: 0.3f32 };
+    let mut seen = 0;
+
+    for _ in 0..60 * crate::game::TICKS_PER_SECOND {
+      let before = spawner.power_ups.len();
+      spawner.update(&level, level.speed, &dino, &mut vec!());
+      if spawner.power_ups.len() > before {
+        seen += 1;
+        let power_up = spawner.power_ups[spawner.power_ups.len() - 1].rect;
+        assert!(spawner.obstacles.iter().all(|obstacle| !obstacle.rect.overlaps(&power_up)));
+      }
+    }
+
+    assert!(seen >= 3, "only {} power-ups in a minute", seen);
   }
 }
@@ -0,0 +1,237 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use macroquad::audio::{load_sound_from_bytes, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound};
+
+use super::events::Event;
+
+// Each cue and music track is looked up with these extensions, in this order.
+const EXTENSIONS: [&str; 2] = ["ogg", "wav"];
+// Music tracks, from the slowest to the fastest: `music-1`, `music-2`...
+const MUSIC: &str = "assets/audio/music";
+const MAX_TRACKS: usize = 8;
+// The music keeps playing this much quieter behind the pause menu.
+const PAUSED_VOLUME: f32 = 0.3f32;
+
+/// A sound effect, played in response to what happened during a tick.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Cue {
+  Jump,
+  Land,
+  Milestone,
+  Death
+}
+
+impl Cue {
+  const ALL: [Cue; 4] = [Cue::Jump, Cue::Land, Cue::Milestone, Cue::Death];
+
+  fn for_event(event: &Event) -> Option<Self> {
+    match event {
+      Event::Jumped => Some(Cue::Jump),
+      Event::Landed => Some(Cue::Land),
+      Event::Milestone(_) => Some(Cue::Milestone),
+      Event::Collision(_) => Some(Cue::Death),
+      _ => None
+    }
+  }
+
+  fn path(& self) -> &'static str {
+    match self {
+      Cue::Jump => "assets/audio/jump",
+      Cue::Land => "assets/audio/land",
+      Cue::Milestone => "assets/audio/milestone",
+      Cue::Death => "assets/audio/death"
+    }
+  }
+}
+
+/// What the music should be doing this frame.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Music {
+  Stopped,
+  /// `intensity` from 0 to 1, faster tracks play as it grows.
+  Playing { intensity: f32, paused: bool }
+}
+
+/// The optional sound pack: whatever is missing, or can't be played, stays silent.
+pub struct Audio {
+  cues: Vec<(Cue, Sound)>,
+  tracks: Vec<Sound>,
+  current: Option<(usize, bool)>,
+  volume: f32
+}
+
+impl Audio {
+  pub async fn load() -> Self {
+    let mut cues = vec!();
+    for cue in Cue::ALL {
+      if let Some(sound) = load(cue.path()).await {
+        cues.push((cue, sound));
+      }
+    }
+
+    // Tracks are numbered from 1, the first one missing ends the list.
+    let mut tracks = vec!();
+    for number in 1..=MAX_TRACKS {
+      match load(&std::fmt::format(format_args!("{}-{}", MUSIC, number))).await {
+        Some(sound) => tracks.push(sound),
+        None => break
+      }
+    }
+
+    Self { cues, tracks, current: None, volume: 1f32 }
+  }
+
+  /// Master volume from 0 to 1, 0 when muted.
+  pub fn set_volume(&mut self, volume: f32) {
+    if volume != self.volume {
+      self.volume = volume;
+      self.apply_music_volume();
+    }
+  }
+
+  /// Plays the cues of a tick's events.
+  pub fn play(& self, events: &[Event]) {
+    if self.volume <= 0f32 {
+      return;
+    }
+
+    for cue in events.iter().filter_map(Cue::for_event) {
+      if let Some((_, sound)) = self.cues.iter().find(|(other, _)| *other == cue) {
+        play_sound(*sound, PlaySoundParams { looped: false, volume: self.volume });
+      }
+    }
+  }
+
+  /// Switches tracks as the run speeds up, a new track starts from its beginning.
+  pub fn update_music(&mut self, music: Music) {
+    let wanted = match music {
+      Music::Playing { intensity, paused } if !self.tracks.is_empty() => Some((track(intensity, self.tracks.len()), paused)),
+      _ => None
+    };
+    if wanted == self.current {
+      return;
+    }
+
+    let previous = self.current.map(|(index, _)| index);
+    self.current = wanted;
+    match (previous, wanted.map(|(index, _)| index)) {
+      (Some(old), Some(new)) if old == new => self.apply_music_volume(),
+      (old, new) => {
+        if let Some(old) = old {
+          stop_sound(self.tracks[old]);
+        }
+        if let Some(new) = new {
+          play_sound(self.tracks[new], PlaySoundParams { looped: true, volume: self.music_volume() });
+        }
+      }
+    }
+  }
+
+  fn music_volume(& self) -> f32 {
+    match self.current {
+      Some((_, true)) => self.volume * PAUSED_VOLUME,
+      _ => self.volume
+    }
+  }
+
+  fn apply_music_volume(& self) {
+    if let Some((index, _)) = self.current {
+      set_sound_volume(self.tracks[index], self.music_volume());
+    }
+  }
+}
+
+/// Which of `count` tracks plays at `intensity`, the last one only at full speed.
+fn track(intensity: f32, count: usize) -> usize {
+  ((intensity.clamp(0f32, 1f32) * (count - 1) as f32).floor() as usize).min(count - 1)
+}
+
+// The decoder panics on anything it can't read, so only files that decode fine get to it.
+async fn load(stem: &str) -> Option<Sound> {
+  for extension in EXTENSIONS {
+    let path = std::fmt::format(format_args!("{}.{}", stem, extension));
+    if !Path::new(&path).exists() {
+      continue;
+    }
+
+    let bytes = match std::fs::read(&path) {
+      Ok(bytes) => bytes,
+      Err(e) => {
+        eprintln!("Couldn't read {}: {}", path, e);
+        return None;
+      }
+    };
+    if let Err(message) = decode(&bytes) {
+      eprintln!("Ignoring {}: {}", path, message);
+      return None;
+    }
+    return load_sound_from_bytes(&bytes).await.map_err(|e| eprintln!("Couldn't load {}: {}", path, e)).ok();
+  }
+
+  None
+}
+
+// Reads the whole file the way the decoder will, without panicking.
+fn decode(bytes: &[u8]) -> Result<(), String> {
+  let mut reader = audrey::Reader::new(Cursor::new(bytes)).map_err(|_| "not an Ogg Vorbis or WAV file".to_owned())?;
+  let channels = reader.description().channel_count();
+  if channels != 1 && channels != 2 {
+    return Err(format!("{} channels, only mono and stereo can be played", channels));
+  }
+
+  reader.samples::<f32>().try_for_each(|sample| sample.map(|_| ())).map_err(|e| format!("broken audio, {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{decode, track, Cue};
+  use crate::game::events::Event;
+
+  #[test]
+  fn events_pick_their_cue() {
+    assert_eq!(Cue::for_event(&Event::Jumped), Some(Cue::Jump));
+    assert_eq!(Cue::for_event(&Event::Milestone(300)), Some(Cue::Milestone));
+    assert_eq!(Cue::for_event(&Event::Collision(4)), Some(Cue::Death));
+    assert_eq!(Cue::for_event(&Event::ObstaclePassed(4)), None);
+  }
+
+  // A mono 16-bit WAV file of `samples` silent samples, cut short by `missing` bytes.
+  fn wav(samples: u32, missing: usize) -> Vec<u8> {
+    let data = samples * 2;
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend((36 + data).to_le_bytes());
+    bytes.extend(b"WAVEfmt ");
+    bytes.extend(16u32.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend(44_100u32.to_le_bytes());
+    bytes.extend(88_200u32.to_le_bytes());
+    bytes.extend(2u16.to_le_bytes());
+    bytes.extend(16u16.to_le_bytes());
+    bytes.extend(b"data");
+    bytes.extend(data.to_le_bytes());
+    bytes.extend(vec!(0u8; data as usize));
+    bytes.truncate(bytes.len() - missing);
+    bytes
+  }
+
+  #[test]
+  fn only_files_that_decode_get_played() {
+    assert_eq!(decode(&wav(100, 0)), Ok(()));
+    assert!(decode(&wav(100, 51)).is_err());
+    assert!(decode(b"OggS, or so it says").is_err());
+    assert!(decode(b"").is_err());
+  }
+
+  #[test]
+  fn faster_tracks_follow_the_intensity() {
+    assert_eq!(track(0f32, 1), 0);
+    assert_eq!(track(1f32, 1), 0);
+    assert_eq!(track(0f32, 3), 0);
+    assert_eq!(track(0.49f32, 3), 0);
+    assert_eq!(track(0.5f32, 3), 1);
+    assert_eq!(track(0.99f32, 3), 1);
+    assert_eq!(track(1f32, 3), 2);
+  }
+}
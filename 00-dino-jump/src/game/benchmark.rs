@@ -0,0 +1,130 @@
+use std::fmt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use super::{CANVAS, TICKS_PER_SECOND};
+use super::actors::dino::Dino;
+use super::actors::spawner::Spawner;
+use super::collision::Hitboxes;
+use super::controller::Controller;
+use super::difficulty::{Level, Preset};
+use super::simulation::{Simulation, State};
+
+// Runs still going after this long stop there, a good bot could otherwise play forever.
+const MAX_SECONDS: u64 = 300;
+// Obstacles on screen for each line of the profile, as many as fit for the last one.
+const PROFILE_COUNTS: [usize; 6] = [0, 1, 2, 4, 8, usize::MAX];
+const PROFILE_TICKS: u32 = 200_000;
+
+/// Scores of many headless runs, one per seed.
+pub struct Report {
+  first_seed: u64,
+  preset: Preset,
+  scores: Vec<u32>,
+  /// Runs stopped by the time limit rather than by a collision.
+  survived: usize
+}
+
+/// Lets a controller made by `controller` play `runs` games on consecutive seeds, starting from `first_seed`.
+pub fn run(runs: u32, first_seed: u64, preset: Preset, controller: impl Fn() -> Box<dyn Controller>) -> Report {
+  let mut scores = vec!();
+  let mut survived = 0;
+
+  for seed in (0..runs as u64).map(|run| first_seed.wrapping_add(run)) {
+    let mut simulation = Simulation::new(CANVAS, seed);
+    simulation.set_preset(preset);
+    let mut bot = controller();
+
+    while simulation.state() == State::Playing && simulation.ticks() < MAX_SECONDS * TICKS_PER_SECOND as u64 {
+      let input = bot.input(&simulation);
+      simulation.step(&input);
+    }
+
+    survived += (simulation.state() == State::Playing) as usize;
+    scores.push(simulation.score());
+  }
+
+  scores.sort_unstable();
+  Report { first_seed, preset, scores, survived }
+}
+
+impl Report {
+  /// Score below which `fraction` of the runs fall (nearest rank).
+  fn percentile(& self, fraction: f32) -> u32 {
+    let rank = (fraction * self.scores.len() as f32).ceil() as usize;
+    self.scores.get(rank.saturating_sub(1)).copied().unwrap_or(0)
+  }
+
+  fn mean(& self) -> f32 {
+    self.scores.iter().map(|&score| score as f32).sum::<f32>() / self.scores.len().max(1) as f32
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let runs = self.scores.len();
+    writeln!(f, "{} runs on {}, seeds {} to {}", runs, self.preset.name(), self.first_seed, self.first_seed.wrapping_add(runs.saturating_sub(1) as u64))?;
+    writeln!(f, "  min {}  p10 {}  median {}  p90 {}  max {}  mean {:.1}",
+      self.percentile(0f32), self.percentile(0.1f32), self.percentile(0.5f32),
+      self.percentile(0.9f32), self.percentile(1f32), self.mean())?;
+    write!(f, "  {} still alive after {} seconds", self.survived, MAX_SECONDS)
+  }
+}
+
+/// Cost of the obstacles' share of a tick, against how many are on screen.
+pub struct Profile {
+  ticks: u32,
+  lines: Vec<(usize, Duration)>
+}
+
+/// Times the spawner's collisions and update with a fixed number of obstacles, the world standing still.
+pub fn profile() -> Profile {
+  let dino = Dino::new(&CANVAS);
+  // Nothing moves, so nothing spawns, leaves or gets passed.
+  let level = Level { speed: 0f32, min_gap: 0f32, cluster_probability: 0f32 };
+  let mut events = Vec::with_capacity(32);
+  let mut lines = vec!();
+
+  for count in PROFILE_COUNTS {
+    let mut spawner = Spawner::new(&CANVAS, 0);
+    let count = spawner.fill(count);
+
+    let start = Instant::now();
+    for _ in 0..PROFILE_TICKS {
+      events.clear();
+      spawner.collide(black_box(&dino), Hitboxes::builtin(), &mut events);
+      spawner.update(black_box(&level), level.speed, &dino, &mut events);
+    }
+    black_box(&events);
+    lines.push((count, start.elapsed() / PROFILE_TICKS));
+  }
+
+  Profile { ticks: PROFILE_TICKS, lines }
+}
+
+impl fmt::Display for Profile {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Obstacles  ns per tick  (mean of {} ticks)", self.ticks)?;
+    for (count, duration) in &self.lines {
+      write!(f, "\n{:>9}  {:>11}", count, duration.as_nanos())?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Report;
+  use crate::game::difficulty::Preset;
+
+  #[test]
+  fn percentiles_use_the_nearest_rank() {
+    let report = Report { first_seed: 0, preset: Preset::Normal, scores: (1..=10).map(|score| score * 10).collect(), survived: 0 };
+
+    assert_eq!(report.percentile(0f32), 10);
+    assert_eq!(report.percentile(0.5f32), 50);
+    assert_eq!(report.percentile(0.9f32), 90);
+    assert_eq!(report.percentile(1f32), 100);
+    assert_eq!(report.mean(), 55f32);
+  }
+}
@@ -0,0 +1,85 @@
+use super::actors::dino::{DUCK_HEIGHT, STAND_HEIGHT};
+use super::controller::Controller;
+use super::simulation::{Input, Simulation};
+
+// Seconds before contact to take off: the time a held jump takes to clear a cactus, plus a bit.
+const JUMP_LEAD: f32 = 0.09f32;
+// Seconds before contact to start ducking under a bird.
+const DUCK_LEAD: f32 = 0.25f32;
+
+/// Plays by looking at the obstacles ahead: ducks under birds at head height, jumps over the
+/// rest and holds the jump until the whole cluster is behind.
+#[derive(Default)]
+pub struct Bot {
+  holding: bool
+}
+
+impl Controller for Bot {
+  fn input(&mut self, simulation: &Simulation) -> Input {
+    let dino = simulation.dino().rect;
+    let ground = simulation.viewport().ground();
+    let speed = simulation.speed().max(1f32);
+    let airborne = simulation.dino().is_airborne();
+    let mut input = Input::default();
+
+    for obstacle in simulation.spawner().obstacles() {
+      let rect = obstacle.rect;
+      // Behind the dino already, or high enough to run under.
+      if rect.right() < dino.left() || rect.bottom() < ground - STAND_HEIGHT {
+        continue;
+      }
+
+      let time_to_contact = (rect.left() - dino.right()) / speed;
+      if rect.bottom() < ground - DUCK_HEIGHT {
+        input.duck |= time_to_contact < DUCK_LEAD && !airborne;
+      } else if time_to_contact < JUMP_LEAD {
+        input.jump = true;
+      }
+    }
+
+    // Back on the ground while still holding: let go for a tick, so the next jump is a new press.
+    if input.jump && self.holding && !airborne && simulation.dino().rect.bottom() >= ground {
+      input.jump = false;
+    }
+
+    self.holding = input.jump;
+    input
+  }
+
+  fn restart(&mut self) {
+    self.holding = false;
+  }
+
+  fn name(& self) -> Option<&'static str> {
+    Some("Bot")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Bot;
+  use crate::game::TICKS_PER_SECOND;
+  use crate::game::controller::Controller;
+  use crate::game::difficulty::Preset;
+  use crate::game::simulation::{Simulation, State, Viewport};
+
+  const VIEWPORT: Viewport = Viewport { width: 800f32, height: 600f32 };
+
+  #[test]
+  fn survives_the_first_minutes() {
+    for preset in [Preset::Normal, Preset::Hard] {
+      for seed in 0..5 {
+        let mut simulation = Simulation::new(VIEWPORT, seed);
+        simulation.set_preset(preset);
+        let mut bot = Bot::default();
+
+        while simulation.state() == State::Playing && simulation.ticks() < 120 * TICKS_PER_SECOND as u64 {
+          let input = bot.input(&simulation);
+          simulation.step(&input);
+        }
+
+        assert_eq!(simulation.state(), State::Playing, "lost seed {} on {:?} at {} points", seed, preset, simulation.score());
+      }
+    }
+  }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+use crate::animation;
+use crate::game::TICK;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Shape {
+  Rect { x: f32, y: f32, w: f32, h: f32 },
+  Circle { x: f32, y: f32, radius: f32 }
+}
+
+impl Shape {
+  fn offset(& self, origin: Vec2) -> Self {
+    match *self {
+      Shape::Rect { x, y, w, h } => Shape::Rect { x: x + origin.x, y: y + origin.y, w, h },
+      Shape::Circle { x, y, radius } => Shape::Circle { x: x + origin.x, y: y + origin.y, radius }
+    }
+  }
+
+  fn overlaps(& self, other: &Shape) -> bool {
+    match (*self, *other) {
+      (Shape::Rect { x, y, w, h }, Shape::Rect { x: ox, y: oy, w: ow, h: oh }) =>
+        Rect::new(x, y, w, h).overlaps(&Rect::new(ox, oy, ow, oh)),
+      (Shape::Circle { x, y, radius }, Shape::Circle { x: ox, y: oy, radius: other_radius }) =>
+        vec2(x, y).distance(vec2(ox, oy)) < radius + other_radius,
+      (Shape::Rect { x, y, w, h }, Shape::Circle { x: cx, y: cy, radius }) |
+      (Shape::Circle { x: cx, y: cy, radius }, Shape::Rect { x, y, w, h }) => {
+        let closest = vec2(cx.clamp(x, x + w), cy.clamp(y, y + h));
+        closest.distance(vec2(cx, cy)) < radius
+      }
+    }
+  }
+
+  fn draw(& self, color: Color) {
+    match *self {
+      Shape::Rect { x, y, w, h } => draw_rectangle_lines(x, y, w, h, 2f32, color),
+      Shape::Circle { x, y, radius } => draw_circle_lines(x, y, radius, 2f32, color)
+    }
+  }
+}
+
+/// Several shapes standing for one actor on one frame, shown for `duration` seconds.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Frame {
+  pub duration: f32,
+  pub shapes: Vec<Shape>
+}
+
+/// The hitboxes of a pose, frame by frame. Poses loop, like the clips they're timed with.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Pose {
+  pub frames: Vec<Frame>
+}
+
+impl Pose {
+  /// Index of the frame shown `time` seconds into the pose, looked up the same way as a clip's.
+  pub fn frame_index(& self, time: f32) -> Option<usize> {
+    animation::frame_index(self.frames.iter().map(|frame| frame.duration), true, time)
+  }
+}
+
+/// Compound hitboxes of every pose, authored in `hitboxes.ron` and built into the game, so
+/// replays re-simulate the same way everywhere. Their frame timings are the clip timings of
+/// those poses: sprites with as many frames show the frame with the same index.
+#[derive(Deserialize, Debug)]
+#[serde(transparent)]
+pub struct Hitboxes(HashMap<String, Pose>);
+
+impl Hitboxes {
+  pub fn builtin() -> &'static Self {
+    static HITBOXES: OnceLock<Hitboxes> = OnceLock::new();
+    HITBOXES.get_or_init(|| ron::from_str(include_str!("hitboxes.ron")).expect("Invalid built-in hitboxes"))
+  }
+
+  /// Index of the frame of `pose` shown `time` seconds into it, none for poses without hitboxes.
+  pub fn frame_index(& self, pose: &str, time: f32) -> Option<usize> {
+    self.0.get(pose).and_then(|pose| pose.frame_index(time))
+  }
+
+  /// Number of frames of `pose`, none for poses without hitboxes.
+  pub fn frame_count(& self, pose: &str) -> Option<usize> {
+    self.0.get(pose).map(|pose| pose.frames.len())
+  }
+
+  /// The absolute shapes of an actor, its whole bounding box for poses without hitboxes.
+  /// Collisions are checked every tick, so this doesn't allocate.
+  pub fn shapes(& self, pose: &str, ticks: u32, bounds: Rect) -> impl Iterator<Item = Shape> + '_ {
+    let frame = self.0.get(pose).and_then(|pose| pose.frame_index(ticks as f32 * TICK).map(|index| &pose.frames[index]));
+    let whole = frame.is_none().then_some(Shape::Rect { x: bounds.x, y: bounds.y, w: bounds.w, h: bounds.h });
+
+    frame.map_or(&[][..], |frame| &frame.shapes[..]).iter()
+      .map(move |shape| shape.offset(bounds.point()))
+      .chain(whole)
+  }
+}
+
+/// An actor's pose, the ticks it has spent in it and its bounding box: what its hitboxes are looked up with.
+pub type Collider<'a> = (&'a str, u32, Rect);
+
+pub fn collide(hitboxes: &Hitboxes, a: Collider, b: Collider) -> bool {
+  if !a.2.overlaps(&b.2) {
+    return false;
+  }
+
+  hitboxes.shapes(a.0, a.1, a.2).any(|shape| hitboxes.shapes(b.0, b.1, b.2).any(|other| shape.overlaps(&other)))
+}
+
+/// Debug overlay of an actor's hitbox.
+pub fn draw(hitboxes: &Hitboxes, (pose, ticks, bounds): Collider) {
+  draw_rectangle_lines(bounds.x, bounds.y, bounds.w, bounds.h, 1f32, GRAY);
+  for shape in hitboxes.shapes(pose, ticks, bounds) {
+    shape.draw(LIME);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use macroquad::math::Rect;
+
+  use super::{collide, Hitboxes, Shape};
+
+  #[test]
+  fn builtin_hitboxes_parse() {
+    let hitboxes = Hitboxes::builtin();
+
+    for pose in ["run", "jump", "duck", "cactus", "bird"] {
+      assert!(hitboxes.0.contains_key(pose), "no hitbox for {}", pose);
+    }
+  }
+
+  #[test]
+  fn shapes_overlap() {
+    let rect = Shape::Rect { x: 0f32, y: 0f32, w: 10f32, h: 10f32 };
+    let near = Shape::Circle { x: 14f32, y: 5f32, radius: 5f32 };
+    let corner = Shape::Circle { x: 14f32, y: 14f32, radius: 5f32 };
+
+    assert!(rect.overlaps(&near));
+    assert!(near.overlaps(&rect));
+    assert!(!rect.overlaps(&corner));
+    assert!(near.overlaps(&corner));
+  }
+
+  #[test]
+  fn corners_are_forgiving() {
+    let hitboxes = Hitboxes::builtin();
+    let dino = Rect::new(0f32, 0f32, 50f32, 120f32);
+    // Cactus grazing the dino's front foot corner.
+    let grazing = Rect::new(48f32, 110f32, 40f32, 40f32);
+    let hitting = Rect::new(10f32, 80f32, 40f32, 40f32);
+
+    assert!(!collide(hitboxes, ("run", 0, dino), ("cactus", 0, grazing)));
+    assert!(collide(hitboxes, ("run", 0, dino), ("cactus", 0, hitting)));
+  }
+
+  #[test]
+  fn frames_cycle_through_the_pose() {
+    let hitboxes = Hitboxes::builtin();
+    let bounds = Rect::new(0f32, 0f32, 50f32, 30f32);
+
+    let up = format!("{:?}", hitboxes.shapes("bird", 0, bounds).collect::<Vec<Shape>>());
+    let down = format!("{:?}", hitboxes.shapes("bird", 20, bounds).collect::<Vec<Shape>>());
+
+    assert_ne!(up, down);
+    assert_eq!(up, format!("{:?}", hitboxes.shapes("bird", 40, bounds).collect::<Vec<Shape>>()));
+  }
+
+  #[test]
+  fn unknown_poses_use_the_bounding_box() {
+    let bounds = Rect::new(1f32, 2f32, 3f32, 4f32);
+
+    assert_eq!(format!("{:?}", Hitboxes::builtin().shapes("dead", 0, bounds).collect::<Vec<Shape>>()), format!("{:?}", vec!(Shape::Rect { x: 1f32, y: 2f32, w: 3f32, h: 4f32 })));
+  }
+}
@@ -0,0 +1,53 @@
+use super::controls::{Action, Bindings, Controls};
+use super::replay::Replay;
+use super::simulation::{Input, Simulation};
+
+/// Whatever drives the dino: a player on the keyboard, a replay or a bot.
+pub trait Controller {
+  /// Called once per frame before any tick runs, for controllers that read devices.
+  fn poll(&mut self, _controls: &Controls, _bindings: &Bindings) {}
+
+  /// Buttons held during the next tick of `simulation`.
+  fn input(&mut self, simulation: &Simulation) -> Input;
+
+  /// Called whenever a new run starts.
+  fn restart(&mut self) {}
+
+  /// The run a controller is bound to, its seed and preset are used for every restart.
+  fn replay(& self) -> Option<&Replay> {
+    None
+  }
+
+  /// Shown in the HUD when the dino isn't driven by a human, who alone gets high scores.
+  fn name(& self) -> Option<&'static str> {
+    None
+  }
+}
+
+/// A human on the keyboard, the mouse or a gamepad.
+#[derive(Default)]
+pub struct Keyboard {
+  held: Input,
+  jump_latched: bool
+}
+
+impl Controller for Keyboard {
+  fn poll(&mut self, controls: &Controls, bindings: &Bindings) {
+    // Taps shorter than a frame are latched until a tick sees them, frames may run zero ticks.
+    self.jump_latched |= controls.is_pressed(bindings, Action::Jump);
+    self.held = Input {
+      jump: controls.is_down(bindings, Action::Jump),
+      duck: controls.is_down(bindings, Action::Duck)
+    };
+  }
+
+  fn input(&mut self, _simulation: &Simulation) -> Input {
+    let input = Input { jump: self.held.jump || self.jump_latched, ..self.held };
+    self.jump_latched = false;
+    input
+  }
+
+  fn restart(&mut self) {
+    self.jump_latched = false;
+  }
+}
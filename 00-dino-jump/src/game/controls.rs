@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, HashSet};
+
+use gilrs::{EventType, Gilrs};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Keys that can be bound to an action. F1 and Backspace stay reserved for debugging and text entry,
+/// and the menu keys for the menus.
+const KEYS: [KeyCode; 49] = [
+  KeyCode::Space, KeyCode::Enter, KeyCode::Tab, KeyCode::Escape,
+  KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+  KeyCode::LeftShift, KeyCode::RightShift, KeyCode::LeftControl, KeyCode::RightControl,
+  KeyCode::LeftAlt, KeyCode::RightAlt,
+  KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+  KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::N,
+  KeyCode::O, KeyCode::P, KeyCode::R, KeyCode::T, KeyCode::U,
+  KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+  KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+  KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+  KeyCode::Comma, KeyCode::Period, KeyCode::Slash
+];
+
+/// Mute, versus, settings and quit. Settings saved before they were reserved may still bind them.
+const MENU_KEYS: [KeyCode; 4] = [KeyCode::M, KeyCode::V, KeyCode::S, KeyCode::Q];
+
+const MOUSE_BUTTONS: [MouseButton; 3] = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Action {
+  Jump,
+  Duck,
+  Pause,
+  Restart
+}
+
+impl Action {
+  pub fn name(self) -> &'static str {
+    match self {
+      Action::Jump => "Jump",
+      Action::Duck => "Duck",
+      Action::Pause => "Pause",
+      Action::Restart => "Restart"
+    }
+  }
+}
+
+/// A physical button, written `Key("Space")`, `Mouse("Left")` or `Gamepad(South)` in the settings file.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Binding {
+  Key(#[serde(with = "key_name")] KeyCode),
+  Mouse(#[serde(with = "mouse_name")] MouseButton),
+  Gamepad(gilrs::Button)
+}
+
+impl Binding {
+  pub fn name(self) -> String {
+    match self {
+      Binding::Key(key) => key_name(key),
+      Binding::Mouse(button) => format!("Mouse {:?}", button),
+      Binding::Gamepad(button) => format!("Pad {:?}", button)
+    }
+  }
+
+  fn same_device(self, other: Binding) -> bool {
+    std::mem::discriminant(&self) == std::mem::discriminant(&other)
+  }
+}
+
+/// Which buttons trigger each action, any of them will do.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(transparent)]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+  fn default() -> Self {
+    Self(BTreeMap::from([
+      (Action::Jump, vec!(Binding::Key(KeyCode::Space), Binding::Key(KeyCode::Up), Binding::Mouse(MouseButton::Left), Binding::Gamepad(gilrs::Button::South))),
+      (Action::Duck, vec!(Binding::Key(KeyCode::Down), Binding::Gamepad(gilrs::Button::DPadDown))),
+      (Action::Pause, vec!(Binding::Key(KeyCode::Escape), Binding::Key(KeyCode::P), Binding::Gamepad(gilrs::Button::Start))),
+      (Action::Restart, vec!(Binding::Key(KeyCode::Space), Binding::Key(KeyCode::Enter), Binding::Gamepad(gilrs::Button::South)))
+    ]))
+  }
+}
+
+impl Bindings {
+  /// Player 2 of a versus race, on the other side of the keyboard.
+  pub fn second_player() -> Self {
+    Self(BTreeMap::from([
+      (Action::Jump, vec!(Binding::Key(KeyCode::W))),
+      (Action::Duck, vec!(Binding::Key(KeyCode::LeftShift)))
+    ]))
+  }
+
+  pub fn of(& self, action: Action) -> &[Binding] {
+    self.0.get(&action).map(Vec::as_slice).unwrap_or(&[])
+  }
+
+  /// Unbinds the menu keys, actions left without any button go back to their default ones.
+  pub fn drop_menu_keys(&mut self) {
+    for bindings in self.0.values_mut() {
+      bindings.retain(|binding| !matches!(binding, Binding::Key(key) if MENU_KEYS.contains(key)));
+    }
+    self.0.retain(|_, bindings| !bindings.is_empty());
+  }
+
+  /// Actions missing from a settings file keep their default buttons.
+  pub fn fill_defaults(&mut self, defaults: Bindings) {
+    for (action, bindings) in defaults.0 {
+      self.0.entry(action).or_insert(bindings);
+    }
+  }
+
+  /// Replaces the action's bindings on the same device, e.g. a new key keeps the gamepad button.
+  pub fn rebind(&mut self, action: Action, binding: Binding) {
+    let bindings = self.0.entry(action).or_default();
+    bindings.retain(|other| !other.same_device(binding));
+    bindings.push(binding);
+  }
+}
+
+/// Reads the keyboard, the mouse and the gamepads, so the game asks for actions rather than buttons.
+pub struct Controls {
+  gilrs: Option<Gilrs>,
+  gamepad_pressed: HashSet<gilrs::Button>
+}
+
+impl Controls {
+  pub fn new() -> Self {
+    // Without gamepad support (e.g. no udev), the keyboard and mouse still work.
+    let gilrs = Gilrs::new().map_err(|e| eprintln!("No gamepad support: {}", e)).ok();
+    Self { gilrs, gamepad_pressed: HashSet::new() }
+  }
+
+  /// Collects this frame's gamepad presses, call once at the start of every frame.
+  pub fn poll(&mut self) {
+    self.gamepad_pressed.clear();
+    let Some(gilrs) = &mut self.gilrs else { return };
+
+    while let Some(event) = gilrs.next_event() {
+      if let EventType::ButtonPressed(button, _) = event.event {
+        self.gamepad_pressed.insert(button);
+      }
+    }
+  }
+
+  pub fn is_down(& self, bindings: &Bindings, action: Action) -> bool {
+    bindings.of(action).iter().any(|&binding| match binding {
+      Binding::Key(key) => is_key_down(key),
+      Binding::Mouse(button) => is_mouse_button_down(button),
+      Binding::Gamepad(button) => self.gilrs.as_ref().is_some_and(|gilrs| {
+        gilrs.gamepads().any(|(_, gamepad)| gamepad.is_pressed(button))
+      })
+    })
+  }
+
+  pub fn is_pressed(& self, bindings: &Bindings, action: Action) -> bool {
+    bindings.of(action).iter().any(|&binding| match binding {
+      Binding::Key(key) => is_key_pressed(key),
+      Binding::Mouse(button) => is_mouse_button_pressed(button),
+      Binding::Gamepad(button) => self.gamepad_pressed.contains(&button)
+    })
+  }
+
+  /// Any bindable button pressed this frame, to rebind an action to it.
+  pub fn last_pressed(& self) -> Option<Binding> {
+    let key = get_last_key_pressed().filter(|key| KEYS.contains(key)).map(Binding::Key);
+    let mouse = || MOUSE_BUTTONS.into_iter().find(|&button| is_mouse_button_pressed(button)).map(Binding::Mouse);
+    let gamepad = || self.gamepad_pressed.iter().copied().find(|&button| button != gilrs::Button::Unknown).map(Binding::Gamepad);
+
+    key.or_else(mouse).or_else(gamepad)
+  }
+}
+
+/// Name of a key as written in the settings file, e.g. `Space` or `LeftShift`.
+pub fn key_name(key: KeyCode) -> String {
+  format!("{:?}", key)
+}
+
+// Keys and mouse buttons are saved by name, macroquad's types aren't serializable.
+mod key_name {
+  use macroquad::prelude::KeyCode;
+  use serde::de::Error;
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&super::key_name(*key))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    super::KEYS.iter().chain(&super::MENU_KEYS).copied().find(|&key| super::key_name(key) == name)
+      .ok_or_else(|| D::Error::custom(format!("can't bind key {}", name)))
+  }
+}
+
+mod mouse_name {
+  use macroquad::prelude::MouseButton;
+  use serde::de::Error;
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(button: &MouseButton, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:?}", button))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<MouseButton, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    super::MOUSE_BUTTONS.iter().copied().find(|&button| format!("{:?}", button) == name)
+      .ok_or_else(|| D::Error::custom(format!("can't bind mouse button {}", name)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use macroquad::prelude::{KeyCode, MouseButton};
+
+  use super::{Action, Binding, Bindings};
+
+  #[test]
+  fn survives_a_round_trip_to_text() {
+    let bindings = Bindings::default();
+    let text = ron::to_string(&bindings).unwrap();
+
+    assert!(text.contains("Key(\"Space\")") && text.contains("Mouse(\"Left\")") && text.contains("Gamepad(South)"));
+    assert_eq!(ron::from_str::<Bindings>(&text).unwrap(), bindings);
+  }
+
+  #[test]
+  fn rebinding_keeps_other_devices() {
+    let mut bindings = Bindings::default();
+    bindings.rebind(Action::Jump, Binding::Key(KeyCode::W));
+
+    assert_eq!(bindings.of(Action::Jump), &[
+      Binding::Mouse(MouseButton::Left), Binding::Gamepad(gilrs::Button::South), Binding::Key(KeyCode::W)
+    ]);
+  }
+
+  #[test]
+  fn menu_keys_are_unbound() {
+    let mut bindings: Bindings = ron::from_str("{Jump: [Key(\"W\")], Duck: [Key(\"S\")]}").unwrap();
+    bindings.drop_menu_keys();
+    bindings.fill_defaults(Bindings::second_player());
+
+    assert_eq!(bindings, Bindings::second_player());
+  }
+
+  #[test]
+  fn reserved_keys_are_rejected() {
+    assert!(ron::from_str::<Bindings>("{Jump: [Key(\"F1\")]}").is_err());
+    assert!(ron::from_str::<Bindings>("{Pause: [Mouse(\"Unknown\")]}").is_err());
+  }
+}
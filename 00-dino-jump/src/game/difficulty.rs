@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+const OVERRIDE: &str = "assets/difficulty.ron";
+
+/// Piecewise-linear curve through `(x, value)` keyframes sorted by `x`, flat past both ends.
+/// Read from a list of keyframes, which must have strictly increasing `x`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(try_from = "Vec<(f32, f32)>", into = "Vec<(f32, f32)>")]
+pub struct Curve(pub Vec<(f32, f32)>);
+
+impl From<Curve> for Vec<(f32, f32)> {
+  fn from(curve: Curve) -> Self {
+    curve.0
+  }
+}
+
+impl TryFrom<Vec<(f32, f32)>> for Curve {
+  type Error = String;
+
+  fn try_from(keys: Vec<(f32, f32)>) -> Result<Self, String> {
+    if keys.is_empty() {
+      return Err("a curve needs at least one keyframe".to_owned());
+    }
+    if let Some(window) = keys.windows(2).find(|window| window[0].0.partial_cmp(&window[1].0) != Some(Ordering::Less)) {
+      return Err(format!("keyframes must be in strictly increasing order, {} comes after {}", window[1].0, window[0].0));
+    }
+    Ok(Self(keys))
+  }
+}
+
+impl Curve {
+  pub fn sample(& self, x: f32) -> f32 {
+    let keys = &self.0;
+    let Some(&(first_x, first_value)) = keys.first() else { return 0f32 };
+    if x <= first_x {
+      return first_value;
+    }
+
+    for window in keys.windows(2) {
+      let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+      // Keyframes sharing an `x` would divide by zero, the later one wins.
+      if x <= x1 {
+        return if x1 > x0 { y0 + (y1 - y0) * (x - x0) / (x1 - x0) } else { y1 };
+      }
+    }
+
+    keys[keys.len() - 1].1
+  }
+
+  /// Where `x` lies between the first and the last keyframe, from 0 to 1.
+  pub fn progress(& self, x: f32) -> f32 {
+    match (self.0.first(), self.0.last()) {
+      (Some(&(first, _)), Some(&(last, _))) if last > first => ((x - first) / (last - first)).clamp(0f32, 1f32),
+      _ => 1f32
+    }
+  }
+
+  fn scaled(& self, factor: f32) -> Self {
+    Self(self.0.iter().map(|&(x, value)| (x, value * factor)).collect())
+  }
+}
+
+/// How hard the game is at a given moment of a run.
+#[derive(Clone, Copy, Debug)]
+pub struct Level {
+  /// Scroll speed, in pixels per second.
+  pub speed: f32,
+  /// Shortest distance between two clusters, in pixels, before the fairness check.
+  pub min_gap: f32,
+  /// Chance that a cactus gets another one right behind it.
+  pub cluster_probability: f32
+}
+
+/// Difficulty picked in the settings, it scales the default curves.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Preset {
+  Easy,
+  #[default]
+  Normal,
+  Hard
+}
+
+impl Preset {
+  pub const ALL: [Preset; 3] = [Preset::Easy, Preset::Normal, Preset::Hard];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Preset::Easy => "Easy",
+      Preset::Normal => "Normal",
+      Preset::Hard => "Hard"
+    }
+  }
+
+  /// Reads a preset name, whatever its case.
+  pub fn parse(name: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|preset| preset.name().eq_ignore_ascii_case(name.trim()))
+  }
+}
+
+/// Difficulty curves, keyed by seconds since the start of the run. They come from
+/// `assets/difficulty.ron` when there is one, the built-in `difficulty.ron` otherwise.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Difficulty {
+  pub speed: Curve,
+  pub min_gap: Curve,
+  pub cluster_probability: Curve
+}
+
+impl Difficulty {
+  /// Read once, every simulation afterwards starts from the same curves.
+  pub fn load() -> &'static Self {
+    static DIFFICULTY: OnceLock<Difficulty> = OnceLock::new();
+    DIFFICULTY.get_or_init(|| {
+      if Path::new(OVERRIDE).exists() {
+        match std::fs::read_to_string(OVERRIDE).map_err(|e| e.to_string()).and_then(|text| Self::parse(&text)) {
+          Ok(difficulty) => return difficulty,
+          Err(message) => eprintln!("Ignoring {}: {}", OVERRIDE, message)
+        }
+      }
+
+      Self::builtin()
+    })
+  }
+
+  fn builtin() -> Self {
+    Self::parse(include_str!("difficulty.ron")).expect("Invalid built-in difficulty")
+  }
+
+  fn parse(text: &str) -> Result<Self, String> {
+    ron::from_str(text).map_err(|e| e.to_string())
+  }
+
+  pub fn preset(preset: Preset) -> Self {
+    // Speed, gap and cluster chance multipliers.
+    let (speed, min_gap, cluster_probability) = match preset {
+      Preset::Easy => (0.8f32, 1.25f32, 0.5f32),
+      Preset::Normal => (1f32, 1f32, 1f32),
+      Preset::Hard => (1.2f32, 0.85f32, 1.5f32)
+    };
+
+    let normal = Self::default();
+    Self {
+      speed: normal.speed.scaled(speed),
+      min_gap: normal.min_gap.scaled(min_gap),
+      cluster_probability: normal.cluster_probability.scaled(cluster_probability)
+    }
+  }
+
+  /// How far `seconds` is along the speed curve, from 0 to 1.
+  pub fn progress(& self, seconds: f32) -> f32 {
+    self.speed.progress(seconds)
+  }
+
+  pub fn at(& self, seconds: f32) -> Level {
+    Level {
+      speed: self.speed.sample(seconds),
+      min_gap: self.min_gap.sample(seconds),
+      cluster_probability: self.cluster_probability.sample(seconds)
+    }
+  }
+}
+
+impl Default for Difficulty {
+  fn default() -> Self {
+    Self::load().clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Curve, Difficulty, Preset};
+
+  #[test]
+  fn curve_interpolates_between_keyframes() {
+    let curve = Curve(vec!((0f32, 10f32), (10f32, 20f32), (20f32, 0f32)));
+
+    assert_eq!(curve.sample(-5f32), 10f32);
+    assert_eq!(curve.sample(5f32), 15f32);
+    assert_eq!(curve.sample(15f32), 10f32);
+    assert_eq!(curve.sample(100f32), 0f32);
+    assert_eq!(curve.progress(5f32), 0.25f32);
+    assert_eq!(curve.progress(100f32), 1f32);
+  }
+
+  #[test]
+  fn curve_survives_keyframes_sharing_an_x() {
+    let curve = Curve(vec!((0f32, 10f32), (10f32, 20f32), (10f32, 30f32)));
+
+    assert_eq!(curve.sample(10f32), 20f32);
+    assert_eq!(curve.sample(20f32), 30f32);
+    assert!(curve.sample(5f32).is_finite());
+  }
+
+  #[test]
+  fn keyframes_must_increase() {
+    let builtin = include_str!("difficulty.ron");
+    assert!(Difficulty::parse(builtin).is_ok());
+
+    let error = Difficulty::parse(&builtin.replace("(30, 560)", "(0, 560)")).unwrap_err();
+    assert!(error.contains("strictly increasing"), "{}", error);
+    assert!(Difficulty::parse(&builtin.replace("[(0, 0), (20, 0.2), (120, 0.4)]", "[]")).is_err());
+  }
+
+  #[test]
+  fn default_speeds_up() {
+    let difficulty = Difficulty::default();
+
+    assert!(difficulty.at(0f32).speed < difficulty.at(60f32).speed);
+    assert!(difficulty.at(60f32).speed < difficulty.at(600f32).speed);
+  }
+
+  #[test]
+  fn harder_presets_are_faster_and_denser() {
+    let (easy, normal, hard) = (Difficulty::preset(Preset::Easy), Difficulty::preset(Preset::Normal), Difficulty::preset(Preset::Hard));
+
+    assert!(easy.at(30f32).speed < normal.at(30f32).speed && normal.at(30f32).speed < hard.at(30f32).speed);
+    assert!(easy.at(30f32).min_gap > normal.at(30f32).min_gap && normal.at(30f32).min_gap > hard.at(30f32).min_gap);
+  }
+}
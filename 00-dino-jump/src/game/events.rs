@@ -0,0 +1,19 @@
+use super::actors::obstacle::ObstacleId;
+use super::actors::power_up::PowerUpKind;
+
+/// Something that happened during a tick, for scoring and for whoever else listens (sound, stats...).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+  ObstacleSpawned(ObstacleId),
+  /// The obstacle went past the dino without touching it.
+  ObstaclePassed(ObstacleId),
+  Collision(ObstacleId),
+  /// The dino hit the obstacle, but its shield took the hit and the obstacle is gone.
+  ShieldBroken(ObstacleId),
+  PowerUpCollected(PowerUpKind),
+  /// The dino left the ground.
+  Jumped,
+  Landed,
+  /// The score reached another hundred points, the new score.
+  Milestone(u32)
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::actors::dino::STAND_HEIGHT;
+use super::controller::Controller;
+use super::simulation::{Input, Simulation};
+
+// Bump whenever the sensors or the network's shape change, older genomes can't be read.
+const VERSION: u32 = 1;
+pub const INPUTS: usize = 9;
+pub const HIDDEN: usize = 8;
+pub const OUTPUTS: usize = 2;
+/// Weights and biases of both layers.
+pub const WEIGHTS: usize = (INPUTS + 1) * HIDDEN + (HIDDEN + 1) * OUTPUTS;
+
+/// Weights of a small fully connected network: sensors, one tanh hidden layer, jump and duck.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Genome {
+  version: u32,
+  pub weights: Vec<f32>
+}
+
+impl Genome {
+  pub fn new(weights: Vec<f32>) -> Self {
+    assert_eq!(weights.len(), WEIGHTS);
+    Self { version: VERSION, weights }
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let genome: Self = ron::from_str(&text).map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+
+    if genome.version != VERSION || genome.weights.len() != WEIGHTS {
+      return Err(format!("{} was trained for another version of the game", path.display()));
+    }
+    Ok(genome)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+  }
+
+  /// Runs the network, outputs are in `-1.0..1.0`.
+  pub fn think(& self, inputs: &[f32; INPUTS]) -> [f32; OUTPUTS] {
+    let (hidden_weights, output_weights) = self.weights.split_at((INPUTS + 1) * HIDDEN);
+
+    let mut hidden = [0f32; HIDDEN];
+    for (neuron, weights) in hidden.iter_mut().zip(hidden_weights.chunks(INPUTS + 1)) {
+      *neuron = (weights[INPUTS] + weights.iter().zip(inputs).map(|(w, x)| w * x).sum::<f32>()).tanh();
+    }
+
+    let mut outputs = [0f32; OUTPUTS];
+    for (output, weights) in outputs.iter_mut().zip(output_weights.chunks(HIDDEN + 1)) {
+      *output = (weights[HIDDEN] + weights.iter().zip(&hidden).map(|(w, x)| w * x).sum::<f32>()).tanh();
+    }
+
+    outputs
+  }
+}
+
+/// What the network sees of the world, every value roughly in `-1.0..1.0`.
+pub fn sense(simulation: &Simulation, jump_held: bool) -> [f32; INPUTS] {
+  let viewport = simulation.viewport();
+  let ground = viewport.ground();
+  let dino = simulation.dino().rect;
+
+  // The next two obstacles not yet behind the dino.
+  let mut ahead = simulation.spawner().obstacles().iter()
+    .map(|obstacle| obstacle.rect)
+    .filter(|rect| rect.right() >= dino.left());
+  let next = ahead.next();
+  let after = ahead.next();
+
+  let distance = |rect: Option<macroquad::prelude::Rect>| {
+    rect.map_or(1f32, |rect| ((rect.left() - dino.right()) / viewport.width).min(1f32))
+  };
+
+  [
+    distance(next),
+    next.map_or(0f32, |rect| (ground - rect.bottom()) / STAND_HEIGHT),
+    next.map_or(0f32, |rect| rect.h / STAND_HEIGHT),
+    next.map_or(0f32, |rect| rect.w / 100f32),
+    distance(after),
+    simulation.speed() / 1000f32,
+    (ground - dino.bottom()) / STAND_HEIGHT,
+    if simulation.dino().is_airborne() { 1f32 } else { 0f32 },
+    if jump_held { 1f32 } else { 0f32 }
+  ]
+}
+
+/// Plays with a trained genome.
+pub struct NeuralBot {
+  genome: Genome,
+  jump_held: bool
+}
+
+impl NeuralBot {
+  pub fn new(genome: Genome) -> Self {
+    Self { genome, jump_held: false }
+  }
+}
+
+impl Controller for NeuralBot {
+  fn input(&mut self, simulation: &Simulation) -> Input {
+    let [jump, duck] = self.genome.think(&sense(simulation, self.jump_held));
+    self.jump_held = jump > 0f32;
+    Input { jump: self.jump_held, duck: duck > 0f32 }
+  }
+
+  fn restart(&mut self) {
+    self.jump_held = false;
+  }
+
+  fn name(& self) -> Option<&'static str> {
+    Some("Neural")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Genome, INPUTS, WEIGHTS};
+
+  #[test]
+  fn outputs_follow_the_weights() {
+    let zero = Genome::new(vec!(0f32; WEIGHTS));
+    assert_eq!(zero.think(&[1f32; INPUTS]), [0f32, 0f32]);
+
+    // Only output biases: always jump, never duck.
+    let mut weights = vec!(0f32; WEIGHTS);
+    weights[WEIGHTS - 10] = 2f32;
+    weights[WEIGHTS - 1] = -2f32;
+    let [jump, duck] = Genome::new(weights).think(&[0.5f32; INPUTS]);
+    assert!(jump > 0.9f32 && duck < -0.9f32);
+  }
+
+  #[test]
+  fn survives_a_round_trip_to_text() {
+    let genome = Genome::new((0..WEIGHTS).map(|index| index as f32 * 0.01f32).collect());
+    let text = ron::to_string(&genome).unwrap();
+
+    assert_eq!(ron::from_str::<Genome>(&text).unwrap(), genome);
+  }
+}
@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::PathBuf;
+
+use macroquad::prelude::Color;
+
+use super::actors::{Actor, World};
+use super::actors::dino::Dino;
+use super::actors::scene::{Scene, Setup};
+use super::difficulty::{Difficulty, Preset};
+use super::replay::{Player, Replay};
+use super::simulation::{Simulation, State, Viewport};
+use super::sprites::Sprites;
+use super::themes::Palette;
+
+// How opaque the ghost is drawn.
+const ALPHA: f32 = 0.35f32;
+
+/// The best run on a seed, re-simulated in a world of its own next to the live one.
+/// Its obstacles are never drawn and never touch the live dino.
+pub struct Ghost {
+  simulation: Simulation,
+  player: Player
+}
+
+impl Ghost {
+  pub fn new(replay: Replay) -> Self {
+    let mut simulation = Simulation::new(replay.viewport, replay.seed);
+    replay.configure(&mut simulation);
+
+    Self { simulation, player: Player::new(replay) }
+  }
+
+  /// Advances by one tick with the live run, a ghost that crashed stays where it fell.
+  pub fn step(&mut self) {
+    if self.simulation.state() == State::Playing {
+      let input = self.player.input_at(self.simulation.ticks());
+      self.simulation.step(&input);
+    }
+  }
+
+  pub fn dino(& self) -> &Dino {
+    self.simulation.dino()
+  }
+}
+
+/// Follows the live run tick by tick, drawn faded behind it.
+impl Actor for Ghost {
+  fn update(&mut self, _world: &World) {
+    self.step();
+  }
+
+  fn draw(& self, sprites: &Sprites, palette: &Palette) {
+    let faded = |color: Color| Color { a: color.a * ALPHA, ..color };
+    let palette = Palette { tint: faded(palette.tint), dino: faded(palette.dino), highlight: faded(palette.highlight), ..*palette };
+    self.dino().draw(sprites, &palette);
+  }
+
+  fn z_order(& self) -> i32 {
+    -1
+  }
+}
+
+/// Races the personal best on the run's seed, when the settings ask for it. Replays already show
+/// a run of their own and races have a rival already, there's no ghost next to them.
+pub fn populate(setup: &Setup, scene: &mut Scene) {
+  if !setup.settings.ghost || setup.rival {
+    return;
+  }
+
+  let simulation = setup.simulation;
+  if let Some(replay) = load(simulation.seed(), simulation.preset(), simulation.viewport()) {
+    scene.set_score_to_beat(replay.score);
+    scene.add(Box::new(Ghost::new(replay)));
+  }
+}
+
+/// The personal best on `seed` and `preset`, if one was recorded on a playfield of the same size
+/// and with the same difficulty curves.
+pub fn load(seed: u64, preset: Preset, viewport: Viewport) -> Option<Replay> {
+  let path = path(seed, preset)?;
+  if !path.exists() {
+    return None;
+  }
+
+  match Replay::load(&path) {
+    // Obstacles spawn relative to the playfield, another size is another run. So are other curves.
+    Ok(replay) if replay.difficulty != Difficulty::preset(preset) => {
+      eprintln!("Ignoring ghost: {} was recorded with other difficulty curves", path.display());
+      None
+    },
+    Ok(replay) => Some(replay).filter(|replay| replay.viewport == viewport),
+    Err(message) => {
+      eprintln!("Ignoring ghost: {}", message);
+      None
+    }
+  }
+}
+
+/// Keeps `replay` as the ghost of its seed when it beats the current one, returns whether it did.
+pub fn save(replay: &Replay) -> Result<bool, String> {
+  let path = path(replay.seed, replay.preset).ok_or("No data directory to save ghosts to")?;
+  if load(replay.seed, replay.preset, replay.viewport).is_some_and(|best| best.score >= replay.score) {
+    return Ok(false);
+  }
+
+  if let Some(directory) = path.parent() {
+    fs::create_dir_all(directory).map_err(|e| format!("Couldn't create {}: {}", directory.display(), e))?;
+  }
+  replay.save(&path).map(|_| true)
+}
+
+fn path(seed: u64, preset: Preset) -> Option<PathBuf> {
+  let file = std::fmt::format(format_args!("{}-{}.ron", seed, preset.name().to_lowercase()));
+  dirs::data_dir().map(|directory| directory.join("dino-jump").join("ghosts").join(file))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Ghost;
+  use crate::game::bot::Bot;
+  use crate::game::controller::Controller;
+  use crate::game::difficulty::Preset;
+  use crate::game::replay::Replay;
+  use crate::game::simulation::{Input, Simulation, State, Viewport};
+
+  const VIEWPORT: Viewport = Viewport { width: 800f32, height: 600f32 };
+
+  #[test]
+  fn follows_the_recorded_run() {
+    let mut simulation = Simulation::new(VIEWPORT, 5);
+    let mut replay = Replay::new(5, VIEWPORT, Preset::Normal);
+    let mut bot = Bot::default();
+    let mut trail = vec!();
+
+    while simulation.ticks() < 3000 {
+      let input = bot.input(&simulation);
+      replay.record(simulation.ticks(), &input);
+      simulation.step(&input);
+      trail.push(simulation.dino().rect);
+    }
+    replay.finish(&simulation);
+
+    let mut ghost = Ghost::new(replay);
+    for rect in trail {
+      ghost.step();
+      assert_eq!(ghost.dino().rect, rect);
+    }
+  }
+
+  #[test]
+  fn stops_where_it_crashed() {
+    // Standing still, the ghost crashes into the first obstacle.
+    let mut simulation = Simulation::new(VIEWPORT, 5);
+    let replay = Replay::new(5, VIEWPORT, Preset::Normal);
+    while simulation.state() == State::Playing {
+      simulation.step(&Input::default());
+    }
+
+    let mut ghost = Ghost::new(replay);
+    for _ in 0..simulation.ticks() + 100 {
+      ghost.step();
+    }
+    assert_eq!(ghost.simulation.state(), State::GameOver);
+    assert_eq!(ghost.simulation.ticks(), simulation.ticks());
+  }
+}
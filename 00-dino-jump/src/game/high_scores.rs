@@ -0,0 +1,186 @@
+use std::cmp::Reverse;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::storage::{self, Versioned};
+
+const CAPACITY: usize = 10;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+#[serde(default)]
+pub struct Entry {
+  pub name: String,
+  pub score: u32,
+  pub seed: u64,
+  /// Seconds since the Unix epoch.
+  pub date: u64
+}
+
+impl Entry {
+  pub fn new(name: &str, score: u32, seed: u64) -> Self {
+    let date = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Self { name: name.to_owned(), score, seed, date }
+  }
+}
+
+/// The local top 10, best first, saved as RON in the user's data directory.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct HighScores {
+  version: u32,
+  entries: Vec<Entry>
+}
+
+impl HighScores {
+  /// Loads the table from the user's data directory, an unreadable save starts a new one.
+  pub fn load() -> Self {
+    path().and_then(|path| storage::load(&path, "high scores", Self::parse)).unwrap_or_default()
+  }
+
+  fn parse(text: &str) -> Result<Self, String> {
+    let mut high_scores: Self = storage::parse(text)?;
+    high_scores.entries.sort_by_key(|entry| Reverse(entry.score));
+    high_scores.entries.truncate(CAPACITY);
+    Ok(high_scores)
+  }
+
+  pub fn save(&mut self) -> Result<(), String> {
+    storage::save(self, &path().ok_or("No data directory to save high scores to")?)
+  }
+
+  pub fn entries(& self) -> &[Entry] {
+    &self.entries
+  }
+
+  pub fn qualifies(& self, score: u32) -> bool {
+    score > 0 && (self.entries.len() < CAPACITY || self.entries.iter().any(|entry| score > entry.score))
+  }
+
+  /// Inserts a new entry below the ones with the same score, returns its rank if it made the table.
+  pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+    let rank = self.entries.iter().position(|other| entry.score > other.score).unwrap_or(self.entries.len());
+    if rank >= CAPACITY {
+      return None;
+    }
+
+    self.entries.insert(rank, entry);
+    self.entries.truncate(CAPACITY);
+    Some(rank)
+  }
+}
+
+impl Versioned for HighScores {
+  const VERSION: u32 = 1;
+
+  fn version(& self) -> u32 {
+    self.version
+  }
+
+  fn set_version(&mut self, version: u32) {
+    self.version = version;
+  }
+}
+
+fn path() -> Option<PathBuf> {
+  dirs::data_dir().map(|directory| directory.join("dino-jump").join("high-scores.ron"))
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD` (UTC).
+pub fn format_date(seconds: u64) -> String {
+  // Howard Hinnant's days-to-civil algorithm.
+  let z = (seconds / 86_400) as i64 + 719_468;
+  let era = z.div_euclid(146_097);
+  let day_of_era = z - era * 146_097;
+  let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let shifted_month = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+  let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{format_date, Entry, HighScores, CAPACITY};
+  use crate::game::storage::{self, Versioned};
+
+  fn entry(name: &str, score: u32) -> Entry {
+    Entry { name: name.to_owned(), score, seed: 0, date: 0 }
+  }
+
+  #[test]
+  fn keeps_the_best_ten_in_order() {
+    let mut high_scores = HighScores::default();
+    for score in 1..=12 {
+      high_scores.insert(entry("dino", score * 10));
+    }
+
+    let scores: Vec<u32> = high_scores.entries().iter().map(|entry| entry.score).collect();
+    assert_eq!(scores, vec!(120, 110, 100, 90, 80, 70, 60, 50, 40, 30));
+    assert!(!high_scores.qualifies(30));
+    assert!(high_scores.qualifies(40));
+  }
+
+  #[test]
+  fn ties_go_below_older_entries() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(entry("first", 50));
+
+    assert_eq!(high_scores.insert(entry("second", 50)), Some(1));
+    assert_eq!(high_scores.insert(entry("best", 60)), Some(0));
+  }
+
+  #[test]
+  fn full_table_rejects_low_scores() {
+    let mut high_scores = HighScores::default();
+    for _ in 0..CAPACITY {
+      high_scores.insert(entry("dino", 100));
+    }
+
+    assert_eq!(high_scores.insert(entry("late", 100)), None);
+    assert!(!high_scores.qualifies(0));
+  }
+
+  #[test]
+  fn reads_saves_missing_fields() {
+    let text = r#"(
+      entries: [
+        (name: "old", score: 30),
+        (name: "new", score: 70, seed: 4, date: 0),
+      ],
+    )"#;
+
+    let high_scores = HighScores::parse(text).unwrap();
+
+    assert_eq!(high_scores.entries(), &[Entry { seed: 4, ..entry("new", 70) }, entry("old", 30)]);
+  }
+
+  #[test]
+  fn newer_saves_are_not_overwritten() {
+    let text = format!(r#"(version: {}, entries: [(name: "new", score: 70, country: "FR")])"#, HighScores::VERSION + 1);
+    let path = std::env::temp_dir().join(format!("dino-jump-high-scores-{}.ron", std::process::id()));
+    std::fs::write(&path, &text).unwrap();
+
+    let mut high_scores = storage::load(&path, "high scores", HighScores::parse).unwrap();
+    assert_eq!(high_scores.entries(), &[entry("new", 70)]);
+    high_scores.insert(entry("old", 30));
+    let saved = storage::save(&mut high_scores, &path);
+    let on_disk = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(saved.is_err());
+    assert_eq!(on_disk, text);
+  }
+
+  #[test]
+  fn formats_dates() {
+    assert_eq!(format_date(0), "1970-01-01");
+    assert_eq!(format_date(951_782_400), "2000-02-29");
+    assert_eq!(format_date(1_792_281_600), "2026-10-18");
+  }
+}
@@ -0,0 +1,168 @@
+use crate::parallax::{Content, LayerConfig, Parallax};
+
+use super::controller::Controller;
+use super::difficulty::Preset;
+use super::simulation::{Simulation, State, Viewport};
+use super::themes::Palette;
+
+/// One dino on a playfield of its own: its simulation, whatever drives it and the scenery behind it.
+/// A versus race stacks several lanes on the same seed, so they all get the same obstacles.
+pub struct Lane {
+  pub simulation: Simulation,
+  pub controller: Box<dyn Controller>,
+  pub background: Parallax
+}
+
+impl Lane {
+  pub fn new(viewport: Viewport, seed: u64, preset: Preset, controller: Box<dyn Controller>, palette: &Palette) -> Self {
+    let mut simulation = Simulation::new(viewport, seed);
+    simulation.set_preset(preset);
+
+    Self { simulation, controller, background: Parallax::new(&background_layers(&viewport, palette), viewport.width) }
+  }
+}
+
+/// How a versus round ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+  /// Index of the winning lane.
+  Winner(usize),
+  Draw
+}
+
+/// A round is over as soon as a dino crashes. The best score among the survivors wins, it's a
+/// draw when every dino crashed on the same tick or survivors are tied.
+pub fn outcome<'a>(simulations: impl Iterator<Item = &'a Simulation>) -> Option<Outcome> {
+  let mut crashed = false;
+  let mut best: Option<(usize, u32)> = None;
+  let mut tied = false;
+
+  for (index, simulation) in simulations.enumerate() {
+    if simulation.state() == State::GameOver {
+      crashed = true;
+      continue;
+    }
+
+    match best {
+      Some((_, score)) if score > simulation.score() => {},
+      Some((_, score)) if score == simulation.score() => tied = true,
+      _ => {
+        best = Some((index, simulation.score()));
+        tied = false;
+      }
+    }
+  }
+
+  match best {
+    _ if !crashed => None,
+    Some((index, _)) if !tied => Some(Outcome::Winner(index)),
+    _ => Some(Outcome::Draw)
+  }
+}
+
+fn background_layers(viewport: &Viewport, palette: &Palette) -> [LayerConfig; 4] {
+  let ground = viewport.ground();
+  [
+    LayerConfig {
+      scroll_factor: 0.05f32,
+      content: Content::Clouds { top: 40f32, bottom: ground * 0.4f32 },
+      density: 5,
+      color: palette.clouds
+    },
+    LayerConfig {
+      scroll_factor: 0.1f32,
+      content: Content::Mountains { baseline: ground, max_height: 200f32 },
+      density: 6,
+      color: palette.far_mountains
+    },
+    LayerConfig {
+      scroll_factor: 0.3f32,
+      content: Content::Mountains { baseline: ground, max_height: 90f32 },
+      density: 8,
+      color: palette.near_mountains
+    },
+    LayerConfig {
+      scroll_factor: 1f32,
+      content: Content::Dots { top: ground + 6f32, bottom: ground + 40f32 },
+      density: 40,
+      color: palette.dust
+    }
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{outcome, Outcome};
+  use crate::game::{LANE, TICK};
+  use crate::game::actors::power_up::PowerUpKind;
+  use crate::game::bot::Bot;
+  use crate::game::controller::Controller;
+  use crate::game::events::Event;
+  use crate::game::simulation::{Input, Simulation, State};
+
+  #[test]
+  fn lanes_on_a_seed_share_their_obstacles() {
+    let mut lanes = [Simulation::new(LANE, 10), Simulation::new(LANE, 10)];
+    let mut bots = [Bot::default(), Bot::default()];
+    let mut scrolled = [0f64, 0f64];
+    let mut spawned = [vec!(), vec!()];
+    let mut slowed_alone = 0;
+
+    // One dino ducks whenever it isn't jumping, the other doesn't, and only the other one collects
+    // slow motion on this seed: the obstacles come all the same. Slow motion slows a lane down, it
+    // doesn't move obstacles, so they're compared by how far into the run they show up.
+    while lanes.iter().all(|lane| lane.state() == State::Playing) && lanes[0].ticks() < 6000 {
+      let input = bots[0].input(&lanes[0]);
+      lanes[0].step(&Input { duck: !input.jump, ..input });
+      let input = bots[1].input(&lanes[1]);
+      lanes[1].step(&input);
+
+      let slowed = lanes.each_ref().map(|lane| lane.dino().effects().has(PowerUpKind::SlowMotion));
+      slowed_alone += (slowed[0] != slowed[1]) as u32;
+      for ((lane, spawned), scrolled) in lanes.iter().zip(spawned.iter_mut()).zip(scrolled.iter_mut()) {
+        *scrolled += (lane.world().speed * TICK) as f64;
+        spawned.extend(lane.events().iter().filter_map(|event| match event {
+          Event::ObstacleSpawned(id) => lane.spawner().obstacles().iter().find(|obstacle| obstacle.id() == *id)
+            .map(|obstacle| (obstacle.kind(), obstacle.rect.y, *scrolled + obstacle.rect.x as f64)),
+          _ => None
+        }));
+      }
+    }
+
+    assert!(slowed_alone > 0, "slow motion never slowed a single lane");
+    let count = spawned[0].len().min(spawned[1].len());
+    assert!(count > 10, "only {} obstacles", count);
+    for (a, b) in spawned[0].iter().zip(spawned[1].iter()) {
+      assert_eq!((a.0, a.1), (b.0, b.1));
+      assert!((a.2 - b.2).abs() < 0.5f64, "obstacle {}px into the run in one lane, {}px in the other", a.2, b.2);
+    }
+  }
+
+  #[test]
+  fn the_first_crash_ends_the_round() {
+    let mut lanes = [Simulation::new(LANE, 8), Simulation::new(LANE, 8)];
+    let mut bot = Bot::default();
+
+    while outcome(lanes.iter()).is_none() {
+      let input = bot.input(&lanes[1]);
+      lanes[0].step(&Input::default());
+      lanes[1].step(&input);
+    }
+
+    assert_eq!(outcome(lanes.iter()), Some(Outcome::Winner(1)));
+    assert_eq!(lanes[1].state(), State::Playing);
+  }
+
+  #[test]
+  fn crashing_together_is_a_draw() {
+    let mut lanes = [Simulation::new(LANE, 8), Simulation::new(LANE, 8)];
+
+    while outcome(lanes.iter()).is_none() {
+      for lane in lanes.iter_mut() {
+        lane.step(&Input::default());
+      }
+    }
+
+    assert_eq!(outcome(lanes.iter()), Some(Outcome::Draw));
+  }
+}
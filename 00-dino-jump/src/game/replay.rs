@@ -0,0 +1,256 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::controller::Controller;
+use super::difficulty::{Difficulty, Preset};
+use super::simulation::{Input, Simulation, State, Viewport};
+
+/// Bumped whenever the simulation changes behaviour, older replays wouldn't re-simulate the same.
+const VERSION: u32 = 7;
+
+/// Input as it changed on a given tick, it stays the same until the next change.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct InputChange {
+  pub tick: u64,
+  pub input: Input
+}
+
+/// Everything needed to re-simulate a run: the seed, the playfield and the inputs.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+  pub version: u32,
+  pub seed: u64,
+  pub viewport: Viewport,
+  pub preset: Preset,
+  /// The preset's curves as they were, `assets/difficulty.ron` can change them from one run to the next.
+  pub difficulty: Difficulty,
+  pub score: u32,
+  pub ticks: u64,
+  pub inputs: Vec<InputChange>
+}
+
+impl Replay {
+  pub fn new(seed: u64, viewport: Viewport, preset: Preset) -> Self {
+    Self {
+      version: VERSION,
+      seed,
+      viewport,
+      preset,
+      difficulty: Difficulty::preset(preset),
+      score: 0,
+      ticks: 0,
+      inputs: vec!()
+    }
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    Self::parse(&text).map_err(|e| format!("Couldn't load {}: {}", path.display(), e))
+  }
+
+  fn parse(text: &str) -> Result<Self, String> {
+    let replay: Self = ron::from_str(text).map_err(|e| e.to_string())?;
+
+    if replay.version != VERSION {
+      return Err(format!("recorded with replay version {}, this build plays version {}", replay.version, VERSION));
+    }
+    Ok(replay)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+  }
+
+  /// Sets a simulation up to play the run again, with the curves it was recorded with.
+  pub fn configure(& self, simulation: &mut Simulation) {
+    simulation.set_difficulty(self.preset, self.difficulty.clone());
+  }
+
+  /// Keeps the input applied on `tick`, only when it differs from the previous one.
+  pub fn record(&mut self, tick: u64, input: &Input) {
+    let previous = self.inputs.last().map(|change| change.input).unwrap_or_default();
+    if previous != *input {
+      self.inputs.push(InputChange { tick, input: *input });
+    }
+  }
+
+  pub fn finish(&mut self, simulation: &Simulation) {
+    self.score = simulation.score();
+    self.ticks = simulation.ticks();
+  }
+}
+
+/// Feeds a replay's inputs back, tick by tick.
+pub struct Player {
+  replay: Replay,
+  cursor: usize,
+  current: Input
+}
+
+impl Player {
+  pub fn new(replay: Replay) -> Self {
+    Self { replay, cursor: 0, current: Input::default() }
+  }
+
+  pub fn rewind(&mut self) {
+    self.cursor = 0;
+    self.current = Input::default();
+  }
+
+  pub fn input_at(&mut self, tick: u64) -> Input {
+    while let Some(change) = self.replay.inputs.get(self.cursor) {
+      if change.tick > tick {
+        break;
+      }
+      self.current = change.input;
+      self.cursor += 1;
+    }
+
+    self.current
+  }
+}
+
+impl Controller for Player {
+  fn input(&mut self, simulation: &Simulation) -> Input {
+    self.input_at(simulation.ticks())
+  }
+
+  fn restart(&mut self) {
+    self.rewind();
+  }
+
+  fn replay(& self) -> Option<&Replay> {
+    Some(&self.replay)
+  }
+
+  fn name(& self) -> Option<&'static str> {
+    Some("Replay")
+  }
+}
+
+/// Re-simulates a replay without any window, returns the score it actually reaches.
+pub fn verify(replay: &Replay) -> Result<u32, String> {
+  let mut simulation = Simulation::new(replay.viewport, replay.seed);
+  replay.configure(&mut simulation);
+  let mut player = Player::new(replay.clone());
+
+  while simulation.state() == State::Playing && simulation.ticks() < replay.ticks {
+    let input = player.input_at(simulation.ticks());
+    simulation.step(&input);
+  }
+
+  if simulation.score() == replay.score && simulation.ticks() == replay.ticks {
+    Ok(simulation.score())
+  } else {
+    Err(format!(
+      "Replay claims {} points in {} ticks, but reached {} points in {} ticks",
+      replay.score, replay.ticks, simulation.score(), simulation.ticks()
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{verify, Player, Replay};
+  use crate::game::difficulty::{Difficulty, Preset};
+  use crate::game::simulation::{Input, Simulation, State, Viewport};
+
+  const VIEWPORT: Viewport = Viewport { width: 800f32, height: 600f32 };
+
+  // Records a run where the dino jumps on a fixed rhythm, whatever happens.
+  fn record(seed: u64, preset: Preset) -> Replay {
+    let mut simulation = Simulation::new(VIEWPORT, seed);
+    simulation.set_preset(preset);
+    let mut replay = Replay::new(seed, VIEWPORT, preset);
+
+    while simulation.state() == State::Playing {
+      let input = Input { jump: simulation.ticks() % 90 < 10, duck: false };
+      replay.record(simulation.ticks(), &input);
+      simulation.step(&input);
+    }
+    replay.finish(&simulation);
+
+    replay
+  }
+
+  #[test]
+  fn only_changes_are_recorded() {
+    let mut replay = Replay::new(1, VIEWPORT, Preset::Normal);
+    replay.record(0, &Input { jump: false, duck: false });
+    replay.record(1, &Input { jump: true, duck: false });
+    replay.record(2, &Input { jump: true, duck: false });
+    replay.record(3, &Input { jump: false, duck: false });
+
+    assert_eq!(replay.inputs.iter().map(|change| change.tick).collect::<Vec<u64>>(), vec!(1, 3));
+  }
+
+  #[test]
+  fn player_holds_inputs_between_changes() {
+    let mut replay = Replay::new(1, VIEWPORT, Preset::Normal);
+    replay.record(5, &Input { jump: true, duck: false });
+    replay.record(8, &Input { jump: false, duck: false });
+    let mut player = Player::new(replay);
+
+    let inputs: Vec<bool> = (0..10).map(|tick| player.input_at(tick).jump).collect();
+
+    assert_eq!(inputs, vec!(false, false, false, false, false, true, true, true, false, false));
+  }
+
+  #[test]
+  fn recorded_run_verifies() {
+    let replay = record(99, Preset::Normal);
+
+    assert_eq!(verify(&replay), Ok(replay.score));
+  }
+
+  #[test]
+  fn tampered_score_fails_verification() {
+    let mut replay = record(99, Preset::Normal);
+    replay.score += 10;
+
+    assert!(verify(&replay).is_err());
+  }
+
+  #[test]
+  fn survives_a_round_trip_to_text() {
+    let replay = record(7, Preset::Normal);
+    let text = ron::to_string(&replay).unwrap();
+    let parsed: Replay = ron::from_str(&text).unwrap();
+
+    assert_eq!(verify(&parsed), Ok(replay.score));
+  }
+
+  #[test]
+  fn other_versions_are_rejected() {
+    let mut replay = record(7, Preset::Normal);
+    assert!(Replay::parse(&ron::to_string(&replay).unwrap()).is_ok());
+
+    replay.version += 1;
+    let error = Replay::parse(&ron::to_string(&replay).unwrap()).err().unwrap();
+    assert!(error.contains("replay version"), "{}", error);
+  }
+
+  #[test]
+  fn curves_are_part_of_the_replay() {
+    let mut replay = record(99, Preset::Normal);
+    let text = ron::to_string(&replay).unwrap();
+    assert_eq!(verify(&Replay::parse(&text).unwrap()), Ok(replay.score));
+
+    // As if `assets/difficulty.ron` had changed since: the run still plays back the same.
+    replay.difficulty.speed.0.iter_mut().for_each(|key| key.1 *= 2f32);
+    assert!(verify(&replay).is_err());
+  }
+
+  #[test]
+  fn preset_is_part_of_the_replay() {
+    let mut replay = record(99, Preset::Hard);
+    assert_eq!(verify(&replay), Ok(replay.score));
+
+    replay.preset = Preset::Easy;
+    replay.difficulty = Difficulty::preset(Preset::Easy);
+    assert!(verify(&replay).is_err());
+  }
+}
@@ -0,0 +1,78 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// SplitMix64: tiny and good enough for a game, and unlike the global macroquad RNG,
+/// the same seed yields the same sequence on every machine and every version.
+#[derive(Clone)]
+pub struct Rng {
+  state: u64
+}
+
+impl Rng {
+  pub fn new(seed: u64) -> Self {
+    Self { state: seed }
+  }
+  
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+  }
+  
+  /// Returns a number in `0.0..1.0`.
+  pub fn gen_float(&mut self) -> f32 {
+    (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+  }
+  
+  /// Returns a number in `low..high`.
+  pub fn gen_range(&mut self, low: u32, high: u32) -> u32 {
+    low + (self.next_u64() % (high - low) as u64) as u32
+  }
+  
+  /// Returns a normally distributed number, mean 0 and standard deviation 1 (Box-Muller).
+  pub fn gen_normal(&mut self) -> f32 {
+    let u = 1f32 - self.gen_float();
+    let v = self.gen_float();
+    (-2f32 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+  }
+}
+
+/// A seed short enough to be read off the screen and typed back.
+pub fn random_seed() -> u64 {
+  let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+  Rng::new(nanos).next_u64() % 1_000_000
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Rng;
+
+  #[test]
+  fn same_seed_same_sequence() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    let mut c = Rng::new(43);
+
+    let sequence: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+    assert_eq!(sequence, (0..8).map(|_| b.next_u64()).collect::<Vec<u64>>());
+    assert_ne!(sequence, (0..8).map(|_| c.next_u64()).collect::<Vec<u64>>());
+  }
+
+  #[test]
+  fn gen_range_stays_in_bounds() {
+    let mut rng = Rng::new(7);
+    assert!((0..1000).map(|_| rng.gen_range(3, 9)).all(|n| (3..9).contains(&n)));
+  }
+
+  #[test]
+  fn gen_normal_is_centered() {
+    let mut rng = Rng::new(11);
+    let samples: Vec<f32> = (0..10_000).map(|_| rng.gen_normal()).collect();
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / samples.len() as f32;
+
+    assert!(mean.abs() < 0.05f32, "mean {}", mean);
+    assert!((variance - 1f32).abs() < 0.1f32, "variance {}", variance);
+  }
+}
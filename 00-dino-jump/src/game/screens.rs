@@ -0,0 +1,215 @@
+use macroquad::prelude::*;
+
+use super::controls::{Action, Controls};
+use super::difficulty::Preset;
+use super::settings::Settings;
+use super::simulation::Viewport;
+use super::themes::Palette;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Screen {
+  Title,
+  Playing,
+  Paused,
+  GameOver,
+  Settings
+}
+
+/// Screens opened on top of each other: only the top one gets the input, closing it goes back to the one below.
+pub struct ScreenStack {
+  screens: Vec<Screen>
+}
+
+impl ScreenStack {
+  pub fn new(root: Screen) -> Self {
+    Self { screens: vec!(root) }
+  }
+
+  pub fn top(& self) -> Screen {
+    self.screens[self.screens.len() - 1]
+  }
+
+  /// The screen drawn behind an overlay like the settings.
+  pub fn below(& self) -> Option<Screen> {
+    self.screens.len().checked_sub(2).map(|index| self.screens[index])
+  }
+
+  pub fn push(&mut self, screen: Screen) {
+    self.screens.push(screen);
+  }
+
+  /// Closes the top screen, the root one is never closed.
+  pub fn pop(&mut self) {
+    if self.screens.len() > 1 {
+      self.screens.pop();
+    }
+  }
+
+  pub fn replace(&mut self, screen: Screen) {
+    let top = self.screens.len() - 1;
+    self.screens[top] = screen;
+  }
+
+  /// Closes everything and starts over from `root`.
+  pub fn reset(&mut self, root: Screen) {
+    self.screens.clear();
+    self.screens.push(root);
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Row {
+  Volume,
+  Binding(Action),
+  /// Player 2's, for versus races.
+  SecondBinding(Action),
+  Difficulty,
+  Ghost,
+  Back
+}
+
+const ROWS: [Row; 10] = [
+  Row::Volume,
+  Row::Binding(Action::Jump),
+  Row::Binding(Action::Duck),
+  Row::Binding(Action::Pause),
+  Row::Binding(Action::Restart),
+  Row::SecondBinding(Action::Jump),
+  Row::SecondBinding(Action::Duck),
+  Row::Difficulty,
+  Row::Ghost,
+  Row::Back
+];
+
+/// Up and Down pick a row, Left and Right change it, Enter rebinds an action, Escape goes back.
+pub struct SettingsMenu {
+  selected: usize,
+  rebinding: bool
+}
+
+impl SettingsMenu {
+  pub fn new() -> Self {
+    Self { selected: 0, rebinding: false }
+  }
+
+  /// Returns true once the player leaves the menu.
+  pub fn update(&mut self, settings: &mut Settings, controls: &Controls) -> bool {
+    if self.rebinding {
+      self.update_rebinding(settings, controls);
+      return false;
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+      self.selected = 0;
+      return true;
+    }
+
+    if is_key_pressed(KeyCode::Up) {
+      self.selected = (self.selected + ROWS.len() - 1) % ROWS.len();
+    }
+    if is_key_pressed(KeyCode::Down) {
+      self.selected = (self.selected + 1) % ROWS.len();
+    }
+
+    let step = match (is_key_pressed(KeyCode::Left), is_key_pressed(KeyCode::Right)) {
+      (true, false) => -1,
+      (false, true) => 1,
+      _ => 0
+    };
+    let confirm = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space);
+
+    match ROWS[self.selected] {
+      // Stepping by tenths on the rounded value, so repeated presses don't drift.
+      Row::Volume => settings.volume = (((settings.volume * 10f32).round() + step as f32) / 10f32).clamp(0f32, 1f32),
+      Row::Binding(_) | Row::SecondBinding(_) => self.rebinding = confirm,
+      Row::Difficulty => {
+        let index = Preset::ALL.iter().position(|&preset| preset == settings.difficulty).unwrap_or(1) as i32;
+        let count = Preset::ALL.len() as i32;
+        settings.difficulty = Preset::ALL[(index + step).rem_euclid(count) as usize];
+      },
+      Row::Ghost => settings.ghost ^= step != 0 || confirm,
+      Row::Back => if confirm {
+        self.selected = 0;
+        return true;
+      }
+    }
+
+    false
+  }
+
+  // The new button replaces the ones from the same device, Escape cancels.
+  fn update_rebinding(&mut self, settings: &mut Settings, controls: &Controls) {
+    if is_key_pressed(KeyCode::Escape) {
+      self.rebinding = false;
+      return;
+    }
+
+    let Some(binding) = controls.last_pressed() else { return };
+    match ROWS[self.selected] {
+      Row::Binding(action) => settings.bindings.rebind(action, binding),
+      Row::SecondBinding(action) => settings.second_player.rebind(action, binding),
+      _ => {}
+    }
+    self.rebinding = false;
+  }
+
+  pub fn draw(& self, settings: &Settings, viewport: &Viewport, palette: &Palette) {
+    draw_rectangle(0f32, 0f32, viewport.width, viewport.height, Color { a: 0.85f32, ..palette.background });
+    draw_text("Settings", 40f32, 80f32, 48f32, palette.text);
+
+    for (index, row) in ROWS.iter().enumerate() {
+      let selected = index == self.selected;
+      let value = match row {
+        _ if selected && self.rebinding => "press a button...".to_owned(),
+        Row::Volume => std::fmt::format(format_args!("{}%{}", (settings.volume * 100f32).round(), if settings.muted { " (muted, M)" } else { "" })),
+        Row::Binding(action) => settings.bindings.of(*action).iter().map(|binding| binding.name()).collect::<Vec<String>>().join(", "),
+        Row::SecondBinding(action) => settings.second_player.of(*action).iter().map(|binding| binding.name()).collect::<Vec<String>>().join(", "),
+        Row::Difficulty => settings.difficulty.name().to_owned(),
+        Row::Ghost => (if settings.ghost { "On" } else { "Off" }).to_owned(),
+        Row::Back => String::new()
+      };
+      let label = match row {
+        Row::Volume => "Volume".to_owned(),
+        Row::Binding(action) => action.name().to_owned(),
+        Row::SecondBinding(action) => std::fmt::format(format_args!("P2 {}", action.name())),
+        Row::Difficulty => "Difficulty".to_owned(),
+        Row::Ghost => "Ghost".to_owned(),
+        Row::Back => "Back".to_owned()
+      };
+
+      let line = std::fmt::format(format_args!("{} {:<12} {}", if selected { ">" } else { " " }, label, value));
+      draw_text(&line, 40f32, 140f32 + 40f32 * index as f32, 32f32, if selected { palette.highlight } else { palette.text });
+    }
+
+    draw_text("Difficulty and ghost changes apply to the next run", 40f32, 140f32 + 40f32 * ROWS.len() as f32, 24f32, palette.ground);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Screen, ScreenStack};
+
+  #[test]
+  fn pausing_returns_to_the_run() {
+    let mut screens = ScreenStack::new(Screen::Playing);
+    screens.push(Screen::Paused);
+    screens.push(Screen::Settings);
+
+    assert_eq!(screens.below(), Some(Screen::Paused));
+    screens.pop();
+    screens.pop();
+    assert_eq!(screens.top(), Screen::Playing);
+  }
+
+  #[test]
+  fn root_screen_stays() {
+    let mut screens = ScreenStack::new(Screen::Title);
+    screens.pop();
+
+    assert_eq!(screens.top(), Screen::Title);
+    assert_eq!(screens.below(), None);
+
+    screens.replace(Screen::Playing);
+    assert_eq!(screens.top(), Screen::Playing);
+  }
+}
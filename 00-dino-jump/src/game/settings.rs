@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::controls::Bindings;
+use super::difficulty::Preset;
+use super::storage::{self, Versioned};
+
+/// Player preferences, saved as RON in the user's config directory.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+  version: u32,
+  /// From 0 to 1.
+  pub volume: f32,
+  /// Silences everything without losing the volume.
+  pub muted: bool,
+  pub bindings: Bindings,
+  /// Player 2's, only jump and duck are used.
+  pub second_player: Bindings,
+  /// Only applies from the next run.
+  pub difficulty: Preset,
+  /// Races the personal best on the same seed, when there is one.
+  pub ghost: bool
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      version: Self::VERSION,
+      volume: 0.8f32,
+      muted: false,
+      bindings: Bindings::default(),
+      second_player: Bindings::second_player(),
+      difficulty: Preset::Normal,
+      ghost: true
+    }
+  }
+}
+
+impl Settings {
+  /// Loads the user's settings, an unreadable file falls back to the defaults.
+  pub fn load() -> Self {
+    path().and_then(|path| storage::load(&path, "settings", Self::parse)).unwrap_or_default()
+  }
+
+  fn parse(text: &str) -> Result<Self, String> {
+    let mut settings: Self = storage::parse(text)?;
+    settings.volume = settings.volume.clamp(0f32, 1f32);
+    settings.bindings.drop_menu_keys();
+    settings.bindings.fill_defaults(Bindings::default());
+    settings.second_player.drop_menu_keys();
+    settings.second_player.fill_defaults(Bindings::second_player());
+    Ok(settings)
+  }
+
+  /// Bindings of the player on lane `index`, from 0.
+  pub fn player_bindings(& self, index: usize) -> &Bindings {
+    if index == 0 { &self.bindings } else { &self.second_player }
+  }
+
+  pub fn save(&mut self) -> Result<(), String> {
+    storage::save(self, &path().ok_or("No config directory to save settings to")?)
+  }
+}
+
+impl Versioned for Settings {
+  const VERSION: u32 = 1;
+
+  fn version(& self) -> u32 {
+    self.version
+  }
+
+  fn set_version(&mut self, version: u32) {
+    self.version = version;
+  }
+}
+
+fn path() -> Option<PathBuf> {
+  dirs::config_dir().map(|directory| directory.join("dino-jump").join("settings.ron"))
+}
+
+#[cfg(test)]
+mod tests {
+  use macroquad::prelude::KeyCode;
+
+  use super::Settings;
+  use crate::game::controls::{Action, Binding, Bindings};
+  use crate::game::difficulty::Preset;
+  use crate::game::storage::{self, Versioned};
+
+  #[test]
+  fn survives_a_round_trip_to_text() {
+    let mut bindings = Bindings::default();
+    bindings.rebind(Action::Duck, Binding::Key(KeyCode::LeftShift));
+    let settings = Settings { volume: 0.3f32, muted: true, bindings, difficulty: Preset::Hard, ..Settings::default() };
+    let text = ron::to_string(&settings).unwrap();
+
+    assert_eq!(Settings::parse(&text), Ok(settings));
+  }
+
+  #[test]
+  fn missing_fields_take_their_default() {
+    let settings = Settings::parse("(volume: 3.0, difficulty: Easy)").unwrap();
+
+    assert_eq!(settings.volume, 1f32);
+    assert_eq!(settings.difficulty, Preset::Easy);
+    assert_eq!(settings.bindings, Bindings::default());
+  }
+
+  #[test]
+  fn unbound_actions_keep_their_default() {
+    let settings = Settings::parse("(bindings: {Jump: [Key(\"W\")]})").unwrap();
+
+    assert_eq!(settings.bindings.of(Action::Jump), &[Binding::Key(KeyCode::W)]);
+    assert_eq!(settings.bindings.of(Action::Duck), Bindings::default().of(Action::Duck));
+    assert_eq!(settings.second_player, Bindings::second_player());
+  }
+
+  #[test]
+  fn newer_settings_are_not_overwritten() {
+    let text = format!("(version: {}, volume: 0.5, theme: Dark)", Settings::VERSION + 1);
+    let path = std::env::temp_dir().join(format!("dino-jump-settings-{}.ron", std::process::id()));
+    std::fs::write(&path, &text).unwrap();
+
+    let mut settings = storage::load(&path, "settings", Settings::parse).unwrap();
+    assert_eq!(settings.volume, 0.5f32);
+    settings.muted = true;
+    let saved = storage::save(&mut settings, &path);
+    let on_disk = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(saved.is_err());
+    assert_eq!(on_disk, text);
+  }
+}
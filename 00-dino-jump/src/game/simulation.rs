@@ -0,0 +1,407 @@
+use serde::{Deserialize, Serialize};
+
+use super::actors::{Actor, World};
+use super::actors::dino::{Dino, STAND_HEIGHT};
+use super::actors::power_up::PowerUpKind;
+use super::actors::spawner::Spawner;
+use super::collision::Hitboxes;
+use super::difficulty::{Difficulty, Level, Preset};
+use super::events::Event;
+use super::TICK;
+
+// Every this many points, the run reaches a milestone.
+const MILESTONE: u32 = 100;
+
+// The world scrolls this much slower during slow motion, the dino moves as usual.
+pub const SLOW_MOTION_SCALE: f32 = 0.6f32;
+
+/// Size of the playfield, in pixels.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Viewport {
+  pub width: f32,
+  pub height: f32
+}
+
+impl Viewport {
+  /// The line the dino and cacti stand on.
+  pub fn ground(& self) -> f32 {
+    (self.height + STAND_HEIGHT) * 0.5f32
+  }
+}
+
+/// Buttons held during a tick. Presses are detected by the simulation itself.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(default)]
+pub struct Input {
+  pub jump: bool,
+  pub duck: bool
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum State {
+  Playing,
+  GameOver
+}
+
+/// The whole game logic, advanced one tick at a time without any window.
+/// A run starts as if jump was held: the button that started it must be released before it jumps.
+pub struct Simulation {
+  viewport: Viewport,
+  seed: u64,
+  state: State,
+  score: u32,
+  ticks: u64,
+  /// Ticks spent in slow motion.
+  slow_ticks: u64,
+  dino: Dino,
+  spawner: Spawner,
+  preset: Preset,
+  difficulty: Difficulty,
+  level: Level,
+  events: Vec<Event>,
+  previous_input: Input,
+  world: World
+}
+
+impl Simulation {
+  pub fn new(viewport: Viewport, seed: u64) -> Self {
+    let difficulty = Difficulty::default();
+    Self {
+      viewport,
+      seed,
+      state: State::Playing,
+      score: 0,
+      ticks: 0,
+      slow_ticks: 0,
+      dino: Dino::new(&viewport),
+      spawner: Spawner::new(&viewport, seed),
+      preset: Preset::default(),
+      level: difficulty.at(0f32),
+      difficulty,
+      events: vec!(),
+      previous_input: Input { jump: true, ..Input::default() },
+      world: World::default()
+    }
+  }
+  
+  /// Starts a new run, the same seed always yields the same obstacles.
+  pub fn reset(&mut self, seed: u64) {
+    self.seed = seed;
+    self.score = 0;
+    self.ticks = 0;
+    self.slow_ticks = 0;
+    self.state = State::Playing;
+    self.dino = Dino::new(&self.viewport);
+    self.spawner.reset(seed);
+    self.level = self.difficulty.at(0f32);
+    self.events.clear();
+    self.previous_input = Input { jump: true, ..Input::default() };
+    self.world = World::default();
+  }
+  
+  /// Meant to be set between runs, a replay assumes its preset holds from the first tick.
+  pub fn set_preset(&mut self, preset: Preset) {
+    self.set_difficulty(preset, Difficulty::preset(preset));
+  }
+  
+  /// Like `set_preset`, with curves of its own rather than the preset's current ones, for replays.
+  pub fn set_difficulty(&mut self, preset: Preset, difficulty: Difficulty) {
+    self.preset = preset;
+    self.difficulty = difficulty;
+    self.level = self.difficulty.at(self.world_seconds());
+  }
+  
+  pub fn preset(& self) -> Preset {
+    self.preset
+  }
+  
+  pub fn seed(& self) -> u64 {
+    self.seed
+  }
+  
+  pub fn ticks(& self) -> u64 {
+    self.ticks
+  }
+  
+  /// Scroll speed of the world, in pixels per second, slow motion included.
+  pub fn speed(& self) -> f32 {
+    if self.dino.effects().has(PowerUpKind::SlowMotion) { self.level.speed * SLOW_MOTION_SCALE } else { self.level.speed }
+  }
+  
+  pub fn viewport(& self) -> Viewport {
+    self.viewport
+  }
+  
+  pub fn state(& self) -> State {
+    self.state
+  }
+  
+  pub fn score(& self) -> u32 {
+    self.score
+  }
+  
+  pub fn dino(& self) -> &Dino {
+    &self.dino
+  }
+  
+  pub fn spawner(& self) -> &Spawner {
+    &self.spawner
+  }
+  
+  /// What the actors saw on the last tick, for the ones living outside the simulation.
+  pub fn world(& self) -> World {
+    self.world
+  }
+  
+  /// The dino, obstacles and power-ups, for drawing.
+  pub fn actors(& self) -> impl Iterator<Item = &dyn Actor> {
+    std::iter::once(&self.dino as &dyn Actor)
+      .chain(self.spawner.obstacles().iter().map(|obstacle| obstacle as &dyn Actor))
+      .chain(self.spawner.power_ups().iter().map(|power_up| power_up as &dyn Actor))
+  }
+  
+  /// What happened during the last tick.
+  pub fn events(& self) -> &[Event] {
+    &self.events
+  }
+  
+  pub fn step(&mut self, input: &Input) {
+    let jump_pressed = input.jump && !self.previous_input.jump;
+    self.previous_input = *input;
+    self.events.clear();
+    
+    if self.state == State::GameOver {
+      return;
+    }
+    
+    self.ticks += 1;
+    let was_airborne = self.dino.is_airborne();
+    self.dino.update(input, jump_pressed);
+    match (was_airborne, self.dino.is_airborne()) {
+      (false, true) => self.events.push(Event::Jumped),
+      (true, false) => self.events.push(Event::Landed),
+      _ => {}
+    }
+    // Slow motion slows the scrolling and the world's clock, spawns are still planned for the full speed.
+    if self.dino.effects().has(PowerUpKind::SlowMotion) {
+      self.slow_ticks += 1;
+    }
+    self.level = self.difficulty.at(self.world_seconds());
+    let speed = self.speed();
+    self.world = World { input: *input, jump_pressed, speed };
+    
+    self.spawner.collide(&self.dino, Hitboxes::builtin(), &mut self.events);
+    self.spawner.update(&self.level, speed, &self.dino, &mut self.events);
+    
+    let previous_score = self.score;
+    for index in 0..self.events.len() {
+      match self.events[index] {
+        Event::Collision(id) if self.dino.absorb_hit() => {
+          self.spawner.destroy(id);
+          self.events[index] = Event::ShieldBroken(id);
+        },
+        Event::Collision(_) => {
+          self.state = State::GameOver;
+          self.dino.die();
+        },
+        Event::ObstaclePassed(_) => self.score += 10,
+        Event::PowerUpCollected(kind) => self.dino.collect(kind),
+        Event::ObstacleSpawned(_) | Event::ShieldBroken(_) | Event::Jumped | Event::Landed | Event::Milestone(_) => {}
+      }
+    }
+    
+    if self.score / MILESTONE > previous_score / MILESTONE {
+      self.events.push(Event::Milestone(self.score));
+    }
+  }
+  
+  /// Keeps the dead dino's clip playing once the run is over, one tick at a time. Nothing is
+  /// simulated, so it doesn't count as a tick.
+  pub fn animate(&mut self) {
+    if self.state == State::GameOver {
+      self.dino.animate();
+    }
+  }
+  
+  /// How far the run is along its speed curve, from 0 at the start to 1 once it stops speeding up.
+  pub fn intensity(& self) -> f32 {
+    self.difficulty.progress(self.world_seconds())
+  }
+  
+  // Seconds the world went through, slow motion running them slower. Racing dinos meet the same
+  // difficulty at the same distance into the run, whoever collected slow motion.
+  fn world_seconds(& self) -> f32 {
+    (self.ticks - self.slow_ticks) as f32 * TICK + self.slow_ticks as f32 * TICK * SLOW_MOTION_SCALE
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Input, Simulation, State, Viewport};
+  use crate::game::TICKS_PER_SECOND;
+  use crate::game::actors::Actor;
+  use crate::game::actors::dino::{DUCK_HEIGHT, STAND_HEIGHT};
+  use crate::game::actors::power_up::PowerUpKind;
+  use crate::game::events::Event;
+
+  const VIEWPORT: Viewport = Viewport { width: 800f32, height: 600f32 };
+  const SEED: u64 = 1234;
+  const IDLE: Input = Input { jump: false, duck: false };
+  const JUMP: Input = Input { jump: true, duck: false };
+
+  // Ducks under head-height birds and jumps over anything lower when it's about to reach the dino.
+  fn autoplay(simulation: &Simulation) -> Input {
+    let dino = simulation.dino().rect;
+    let ground = VIEWPORT.ground();
+    let mut input = IDLE;
+
+    for obstacle in simulation.spawner().obstacles() {
+      let distance = obstacle.rect.left() - dino.right();
+      if obstacle.rect.right() < dino.left() || distance >= 150f32 || obstacle.rect.bottom() < ground - STAND_HEIGHT {
+        continue;
+      }
+
+      if obstacle.rect.bottom() < ground - DUCK_HEIGHT {
+        input.duck = true;
+      } else if distance >= 0f32 {
+        input.jump = true;
+      }
+    }
+
+    input
+  }
+
+  #[test]
+  fn jump_returns_to_the_ground() {
+    let mut simulation = Simulation::new(VIEWPORT, SEED);
+    let ground = simulation.dino().rect.y;
+
+    simulation.step(&IDLE);
+    let mut apex = ground;
+    for _ in 0..TICKS_PER_SECOND {
+      simulation.step(&JUMP);
+      apex = apex.min(simulation.dino().rect.y);
+    }
+
+    assert!(ground - apex > 100f32, "jump only reached {}px", ground - apex);
+    assert_eq!(simulation.dino().rect.y, ground);
+  }
+
+  #[test]
+  fn jumps_landings_and_milestones_are_events() {
+    let mut simulation = Simulation::new(VIEWPORT, SEED);
+    let mut events = vec!();
+
+    // Past 30 seconds, only until the dino is back on the ground.
+    while simulation.ticks() < 30 * TICKS_PER_SECOND as u64 || simulation.dino().is_airborne() {
+      let input = autoplay(&simulation);
+      simulation.step(&input);
+      events.extend(simulation.events().iter().copied().filter(|event| matches!(event, Event::Jumped | Event::Landed | Event::Milestone(_))));
+    }
+
+    let jumps = events.iter().filter(|&&event| event == Event::Jumped).count();
+    assert!(jumps > 0);
+    assert_eq!(events.iter().filter(|&&event| event == Event::Landed).count(), jumps);
+    let milestones: Vec<u32> = events.iter().filter_map(|event| match event { Event::Milestone(score) => Some(*score), _ => None }).collect();
+    assert_eq!(milestones, (1..=simulation.score() / 100).map(|hundreds| hundreds * 100).collect::<Vec<u32>>());
+  }
+
+  #[test]
+  fn jump_trajectory_is_reproducible() {
+    let trajectory = || {
+      let mut simulation = Simulation::new(VIEWPORT, SEED);
+      simulation.step(&IDLE);
+      (0..TICKS_PER_SECOND).map(|_| {
+        simulation.step(&JUMP);
+        simulation.dino().rect.y
+      }).collect::<Vec<f32>>()
+    };
+
+    assert_eq!(trajectory(), trajectory());
+  }
+
+  #[test]
+  fn standing_still_collides() {
+    let mut simulation = Simulation::new(VIEWPORT, SEED);
+
+    for _ in 0..5 * TICKS_PER_SECOND {
+      simulation.step(&IDLE);
+    }
+
+    assert_eq!(simulation.state(), State::GameOver);
+    assert_eq!(simulation.score(), 0);
+    assert_eq!(simulation.dino().pose(), "dead");
+
+    // The dead dino's clip plays on, the run doesn't.
+    let ticks = simulation.ticks();
+    let pose_ticks = |simulation: &Simulation| simulation.dino().collider().map(|(_, ticks, _)| ticks);
+    let before = pose_ticks(&simulation);
+    for _ in 0..TICKS_PER_SECOND {
+      simulation.animate();
+    }
+    assert_eq!(simulation.ticks(), ticks);
+    assert_eq!(pose_ticks(&simulation), before.map(|before| before + TICKS_PER_SECOND));
+  }
+
+  #[test]
+  fn shield_takes_one_hit() {
+    let mut simulation = Simulation::new(VIEWPORT, SEED);
+    simulation.dino.collect(PowerUpKind::Shield);
+    let mut broken = 0;
+
+    while simulation.state() == State::Playing {
+      simulation.step(&IDLE);
+      broken += simulation.events().iter().filter(|event| matches!(event, Event::ShieldBroken(_))).count();
+    }
+
+    assert_eq!(broken, 1);
+    assert!(!simulation.dino().effects().has(PowerUpKind::Shield));
+  }
+
+  #[test]
+  fn avoiding_obstacles_scores() {
+    let mut simulation = Simulation::new(VIEWPORT, SEED);
+
+    for _ in 0..20 * TICKS_PER_SECOND {
+      let input = autoplay(&simulation);
+      simulation.step(&input);
+    }
+
+    assert_eq!(simulation.state(), State::Playing);
+    assert!(simulation.score() >= 50, "scored {}", simulation.score());
+  }
+
+  #[test]
+  fn same_seed_same_obstacles() {
+    let run = |seed: u64| {
+      let mut simulation = Simulation::new(VIEWPORT, seed);
+      (0..30 * TICKS_PER_SECOND).map(|_| {
+        let input = autoplay(&simulation);
+        simulation.step(&input);
+        simulation.spawner().obstacles().len()
+      }).collect::<Vec<usize>>()
+    };
+
+    assert_eq!(run(SEED), run(SEED));
+    assert_ne!(run(SEED), run(SEED + 1));
+  }
+
+  #[test]
+  fn reset_starts_a_new_run() {
+    let mut simulation = Simulation::new(VIEWPORT, SEED);
+    while simulation.state() == State::Playing {
+      simulation.step(&IDLE);
+    }
+
+    simulation.step(&JUMP);
+    assert_eq!(simulation.state(), State::GameOver);
+
+    simulation.reset(SEED + 1);
+
+    assert_eq!(simulation.seed(), SEED + 1);
+    assert_eq!(simulation.state(), State::Playing);
+    assert_eq!(simulation.score(), 0);
+    assert_eq!(simulation.ticks(), 0);
+    assert!(simulation.spawner().obstacles().is_empty());
+  }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+use macroquad::prelude::*;
+
+use crate::animation::SpriteSheet;
+use super::collision::Hitboxes;
+
+const DINO_SHEET: &str = "assets/dino.ron";
+const OBSTACLES_SHEET: &str = "assets/obstacles.ron";
+
+/// Which sheet of the art pack an actor's clips are in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sheet {
+  Dino,
+  /// Power-ups too.
+  Obstacles
+}
+
+/// A sheet of the art pack, and which of its clips follow the timing of their hitboxes.
+struct Art {
+  sheet: SpriteSheet,
+  hitbox_timed: HashSet<String>
+}
+
+/// The optional art pack, whatever is missing is drawn as plain shapes.
+#[derive(Default)]
+pub struct Sprites {
+  dino: Option<Art>,
+  obstacles: Option<Art>
+}
+
+impl Sprites {
+  pub async fn load() -> Self {
+    Self {
+      dino: load_art(DINO_SHEET).await,
+      obstacles: load_art(OBSTACLES_SHEET).await
+    }
+  }
+  
+  /// Draws the frame of `clip` shown `time` seconds into it, over `rect`. Returns false when the
+  /// art pack has no such clip, for the actor to draw itself some other way.
+  /// Clips with as many frames as their hitboxes follow their timing, so the art shows what collides.
+  pub fn draw(& self, sheet: Sheet, clip: &str, time: f32, rect: Rect, tint: Color) -> bool {
+    let art = match sheet {
+      Sheet::Dino => self.dino.as_ref(),
+      Sheet::Obstacles => self.obstacles.as_ref()
+    };
+    let frame = art.and_then(|art| if art.hitbox_timed.contains(clip) {
+      Hitboxes::builtin().frame_index(clip, time).and_then(|index| art.sheet.frame_by_index(clip, index))
+    } else {
+      art.sheet.frame(clip, time)
+    });
+    
+    match art.zip(frame) {
+      Some((art, frame)) => {
+        art.sheet.draw(frame, rect, tint);
+        true
+      },
+      None => false
+    }
+  }
+}
+
+async fn load_art(path: &str) -> Option<Art> {
+  // No art pack at all is fine, a broken one is worth a warning.
+  if !std::path::Path::new(path).exists() {
+    return None;
+  }
+  
+  let sheet = SpriteSheet::load(path).await.map_err(|message| eprintln!("{}", message)).ok()?;
+  let hitbox_timed = hitbox_timed(path, sheet.frame_counts(), Hitboxes::builtin());
+  Some(Art { sheet, hitbox_timed })
+}
+
+// The clips that have as many frames as their hitboxes. Those that don't keep their own timing,
+// with a warning: the frame on screen may not be the one that collides.
+fn hitbox_timed<'a>(path: &str, frame_counts: impl Iterator<Item = (&'a str, usize)>, hitboxes: &Hitboxes) -> HashSet<String> {
+  frame_counts
+    .filter(|&(clip, count)| match hitboxes.frame_count(clip) {
+      Some(expected) if expected != count => {
+        eprintln!("Clip {} of {} has {} frames, its hitboxes {}: timing it by its own durations", clip, path, count, expected);
+        false
+      },
+      Some(_) => true,
+      None => false
+    })
+    .map(|(clip, _)| clip.to_string())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::hitbox_timed;
+  use crate::game::collision::Hitboxes;
+
+  #[test]
+  fn clips_follow_hitboxes_with_as_many_frames() {
+    let hitboxes = Hitboxes::builtin();
+    let run = hitboxes.frame_count("run").unwrap();
+    let clips = [("run", run), ("bird", hitboxes.frame_count("bird").unwrap() + 1), ("dead", 1)];
+
+    let timed = hitbox_timed("dino.ron", clips.into_iter(), hitboxes);
+
+    assert!(timed.contains("run"));
+    assert!(!timed.contains("bird"));
+    assert!(!timed.contains("dead"));
+  }
+}
@@ -0,0 +1,380 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use macroquad::prelude::Rect;
+use serde::{Deserialize, Serialize};
+
+use super::TICKS_PER_SECOND;
+use super::actors::obstacle::{ObstacleId, ObstacleKind};
+use super::difficulty::Preset;
+use super::events::Event;
+use super::high_scores::format_date;
+use super::simulation::Simulation;
+use super::storage::{self, Versioned};
+
+// Only the latest runs are kept, the lifetime totals count every run.
+const MAX_RUNS: usize = 1_000;
+/// An obstacle passed closer than this, in pixels between bounding boxes, is a near miss.
+/// Closer than the gap under the highest birds, which are cleared by just running.
+pub const NEAR_MISS: f32 = 8f32;
+
+/// What ended a run.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Cause {
+  Cactus,
+  Bird,
+  /// Left from the pause menu.
+  #[default]
+  Quit
+}
+
+impl Cause {
+  pub fn name(self) -> &'static str {
+    match self {
+      Cause::Cactus => "cactus",
+      Cause::Bird => "bird",
+      Cause::Quit => "quit"
+    }
+  }
+}
+
+/// One run of the player's.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct Run {
+  /// Seconds since the Unix epoch, when the run ended.
+  pub date: u64,
+  pub seed: u64,
+  pub preset: Preset,
+  pub score: u32,
+  pub seconds: f32,
+  pub jumps: u32,
+  pub near_misses: u32,
+  /// In pixels per second, slow motion included.
+  pub max_speed: f32,
+  pub cause: Cause
+}
+
+/// Follows a run tick by tick, to turn it into a [`Run`] once it's over.
+#[derive(Default)]
+pub struct Recorder {
+  jumps: u32,
+  near_misses: u32,
+  max_speed: f32,
+  cause: Cause,
+  /// Closest the dino got to each obstacle it hasn't passed yet.
+  clearances: Vec<(ObstacleId, f32)>
+}
+
+impl Recorder {
+  /// Call after every step of the simulation.
+  pub fn observe(&mut self, simulation: &Simulation) {
+    let dino = simulation.dino().rect;
+    let obstacles = simulation.spawner().obstacles();
+    self.max_speed = self.max_speed.max(simulation.speed());
+
+    for obstacle in obstacles {
+      let clearance = distance(dino, obstacle.rect);
+      match self.clearances.iter_mut().find(|(id, _)| *id == obstacle.id()) {
+        Some((_, closest)) => *closest = closest.min(clearance),
+        None => self.clearances.push((obstacle.id(), clearance))
+      }
+    }
+
+    for event in simulation.events() {
+      match *event {
+        Event::Jumped => self.jumps += 1,
+        Event::ObstaclePassed(passed) => {
+          if self.clearances.iter().any(|&(id, closest)| id == passed && closest < NEAR_MISS) {
+            self.near_misses += 1;
+          }
+          self.clearances.retain(|&(id, _)| id != passed);
+        },
+        Event::Collision(hit) => {
+          if let Some(obstacle) = obstacles.iter().find(|obstacle| obstacle.id() == hit) {
+            self.cause = match obstacle.kind() {
+              ObstacleKind::Cactus => Cause::Cactus,
+              ObstacleKind::Bird => Cause::Bird
+            };
+          }
+        },
+        _ => {}
+      }
+    }
+
+    // Forgets the obstacles a shield smashed.
+    self.clearances.retain(|(id, _)| obstacles.iter().any(|obstacle| obstacle.id() == *id));
+  }
+
+  /// The run so far, quit if the dino is still alive.
+  pub fn finish(& self, simulation: &Simulation) -> Run {
+    Run {
+      date: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+      seed: simulation.seed(),
+      preset: simulation.preset(),
+      score: simulation.score(),
+      seconds: simulation.ticks() as f32 / TICKS_PER_SECOND as f32,
+      jumps: self.jumps,
+      near_misses: self.near_misses,
+      max_speed: self.max_speed,
+      cause: self.cause
+    }
+  }
+}
+
+// Between two boxes, 0 when they overlap.
+fn distance(a: Rect, b: Rect) -> f32 {
+  let dx = (a.left() - b.right()).max(b.left() - a.right()).max(0f32);
+  let dy = (a.top() - b.bottom()).max(b.top() - a.bottom()).max(0f32);
+  dx.hypot(dy)
+}
+
+/// Totals over every run ever recorded.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct Lifetime {
+  pub runs: u32,
+  pub seconds: f32,
+  pub jumps: u32,
+  pub near_misses: u32,
+  pub best_score: u32,
+  pub longest_run: f32,
+  pub max_speed: f32,
+  pub cactus_deaths: u32,
+  pub bird_deaths: u32
+}
+
+impl Lifetime {
+  fn add(&mut self, run: &Run) {
+    self.runs += 1;
+    self.seconds += run.seconds;
+    self.jumps += run.jumps;
+    self.near_misses += run.near_misses;
+    self.best_score = self.best_score.max(run.score);
+    self.longest_run = self.longest_run.max(run.seconds);
+    self.max_speed = self.max_speed.max(run.max_speed);
+    match run.cause {
+      Cause::Cactus => self.cactus_deaths += 1,
+      Cause::Bird => self.bird_deaths += 1,
+      Cause::Quit => {}
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Achievement {
+  Survivor,
+  Daredevil,
+  Thousand,
+  Marathon
+}
+
+impl Achievement {
+  pub const ALL: [Achievement; 4] = [Achievement::Survivor, Achievement::Daredevil, Achievement::Thousand, Achievement::Marathon];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Achievement::Survivor => "Survivor",
+      Achievement::Daredevil => "Daredevil",
+      Achievement::Thousand => "Four digits",
+      Achievement::Marathon => "Marathon"
+    }
+  }
+
+  pub fn description(self) -> &'static str {
+    match self {
+      Achievement::Survivor => "survive 2 minutes",
+      Achievement::Daredevil => "100 near misses",
+      Achievement::Thousand => "score 1000 points",
+      Achievement::Marathon => "play for an hour in total"
+    }
+  }
+
+  fn unlocked(self, run: &Run, lifetime: &Lifetime) -> bool {
+    match self {
+      Achievement::Survivor => run.seconds >= 120f32,
+      Achievement::Daredevil => lifetime.near_misses >= 100,
+      Achievement::Thousand => run.score >= 1_000,
+      Achievement::Marathon => lifetime.seconds >= 3_600f32
+    }
+  }
+}
+
+/// The player's runs, their totals and achievements, saved as RON next to the high scores.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Stats {
+  version: u32,
+  lifetime: Lifetime,
+  achievements: Vec<Achievement>,
+  /// Oldest first.
+  runs: Vec<Run>
+}
+
+impl Stats {
+  /// Loads the stats from the user's data directory, an unreadable save starts new ones.
+  pub fn load() -> Self {
+    path().and_then(|path| storage::load(&path, "stats", storage::parse)).unwrap_or_default()
+  }
+
+  pub fn save(&mut self) -> Result<(), String> {
+    storage::save(self, &path().ok_or("No data directory to save stats to")?)
+  }
+
+  pub fn lifetime(& self) -> &Lifetime {
+    &self.lifetime
+  }
+
+  pub fn achievements(& self) -> &[Achievement] {
+    &self.achievements
+  }
+
+  /// Adds a run to the totals, returns the achievements it unlocked.
+  pub fn record(&mut self, run: Run) -> Vec<Achievement> {
+    self.lifetime.add(&run);
+    let unlocked: Vec<Achievement> = Achievement::ALL.into_iter()
+      .filter(|achievement| !self.achievements.contains(achievement) && achievement.unlocked(&run, &self.lifetime))
+      .collect();
+    self.achievements.extend(&unlocked);
+
+    self.runs.push(run);
+    if self.runs.len() > MAX_RUNS {
+      self.runs.drain(..self.runs.len() - MAX_RUNS);
+    }
+    unlocked
+  }
+
+  /// One line per run, oldest first, under a header.
+  pub fn to_csv(& self) -> String {
+    let mut csv = "date,seed,difficulty,score,seconds,jumps,near_misses,max_speed,cause\n".to_owned();
+    for run in self.runs.iter() {
+      csv += &std::fmt::format(format_args!(
+        "{},{},{},{},{:.2},{},{},{:.0},{}\n",
+        format_date(run.date), run.seed, run.preset.name().to_lowercase(), run.score, run.seconds,
+        run.jumps, run.near_misses, run.max_speed, run.cause.name()
+      ));
+    }
+    csv
+  }
+
+  /// Writes the runs as CSV, returns how many there were.
+  pub fn export(& self, path: &Path) -> Result<usize, String> {
+    fs::write(path, self.to_csv()).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+    Ok(self.runs.len())
+  }
+}
+
+impl Versioned for Stats {
+  const VERSION: u32 = 1;
+
+  fn version(& self) -> u32 {
+    self.version
+  }
+
+  fn set_version(&mut self, version: u32) {
+    self.version = version;
+  }
+}
+
+fn path() -> Option<PathBuf> {
+  dirs::data_dir().map(|directory| directory.join("dino-jump").join("stats.ron"))
+}
+
+#[cfg(test)]
+mod tests {
+  use macroquad::prelude::Rect;
+
+  use super::{distance, Achievement, Cause, Recorder, Run, Stats, NEAR_MISS};
+  use crate::game::{CANVAS, TICKS_PER_SECOND};
+  use crate::game::bot::Bot;
+  use crate::game::controller::Controller;
+  use crate::game::events::Event;
+  use crate::game::simulation::{Input, Simulation, State};
+  use crate::game::storage::{self, Versioned};
+
+  #[test]
+  fn follows_a_run() {
+    let mut simulation = Simulation::new(CANVAS, 11);
+    let mut recorder = Recorder::default();
+    let mut bot = Bot::default();
+    let mut jumps = 0;
+
+    while simulation.ticks() < 60 * TICKS_PER_SECOND as u64 {
+      let input = bot.input(&simulation);
+      simulation.step(&input);
+      recorder.observe(&simulation);
+      jumps += simulation.events().iter().filter(|&&event| event == Event::Jumped).count() as u32;
+    }
+    let run = recorder.finish(&simulation);
+
+    assert_eq!(run.seconds, 60f32);
+    assert_eq!(run.jumps, jumps);
+    assert_eq!(run.cause, Cause::Quit);
+    assert!(run.max_speed >= simulation.speed());
+    assert!(run.near_misses < run.score / 10, "{} near misses out of {} obstacles", run.near_misses, run.score / 10);
+  }
+
+  #[test]
+  fn knows_what_killed_the_dino() {
+    let mut simulation = Simulation::new(CANVAS, 11);
+    let mut recorder = Recorder::default();
+
+    while simulation.state() == State::Playing {
+      simulation.step(&Input::default());
+      recorder.observe(&simulation);
+    }
+
+    assert_ne!(recorder.finish(&simulation).cause, Cause::Quit);
+  }
+
+  #[test]
+  fn close_calls_are_near_misses() {
+    // Running right under a high bird isn't one.
+    assert!(distance(Rect::new(0f32, 10f32, 50f32, 120f32), Rect::new(20f32, -20f32, 50f32, 20f32)) >= NEAR_MISS);
+    assert!(distance(Rect::new(0f32, 0f32, 50f32, 120f32), Rect::new(20f32, 124f32, 40f32, 40f32)) < NEAR_MISS);
+    assert_eq!(distance(Rect::new(0f32, 0f32, 10f32, 10f32), Rect::new(13f32, 14f32, 10f32, 10f32)), 5f32);
+  }
+
+  #[test]
+  fn achievements_unlock_once() {
+    let mut stats = Stats::default();
+    let long = Run { seconds: 130f32, near_misses: 60, score: 400, ..Run::default() };
+
+    assert_eq!(stats.record(long.clone()), vec!(Achievement::Survivor));
+    assert_eq!(stats.record(long), vec!(Achievement::Daredevil));
+    assert_eq!(stats.achievements(), &[Achievement::Survivor, Achievement::Daredevil]);
+    assert_eq!(stats.lifetime().runs, 2);
+    assert_eq!(stats.lifetime().near_misses, 120);
+  }
+
+  #[test]
+  fn exports_runs_as_csv() {
+    let mut stats = Stats::default();
+    stats.record(Run { seed: 7, score: 120, seconds: 12.5f32, jumps: 9, near_misses: 2, max_speed: 512.4f32, cause: Cause::Bird, ..Run::default() });
+
+    let csv = stats.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines, vec!(
+      "date,seed,difficulty,score,seconds,jumps,near_misses,max_speed,cause",
+      "1970-01-01,7,normal,120,12.50,9,2,512,bird"
+    ));
+  }
+
+  #[test]
+  fn newer_stats_are_not_overwritten() {
+    let text = format!("(version: {}, achievements: [Marathon], streaks: [3])", Stats::VERSION + 1);
+    let path = std::env::temp_dir().join(format!("dino-jump-stats-{}.ron", std::process::id()));
+    std::fs::write(&path, &text).unwrap();
+
+    let mut stats: Stats = storage::load(&path, "stats", storage::parse).unwrap();
+    assert_eq!(stats.achievements(), &[Achievement::Marathon]);
+    stats.record(Run::default());
+    let saved = storage::save(&mut stats, &path);
+    let on_disk = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(saved.is_err());
+    assert_eq!(on_disk, text);
+  }
+}
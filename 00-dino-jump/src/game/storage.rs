@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A RON save with a format version. Fields missing from older versions take their default
+/// and fields unknown to this version are ignored, anything more goes through `migrate`.
+pub trait Versioned: Serialize + DeserializeOwned {
+  /// Bump when an existing field changes meaning. New fields only need a default.
+  const VERSION: u32;
+
+  /// Version the save was written with, 0 when it doesn't say.
+  fn version(& self) -> u32;
+
+  fn set_version(&mut self, version: u32);
+
+  /// Brings a save from an older version up to date.
+  fn migrate(&mut self, _from: u32) {}
+}
+
+/// Reads a save, migrating it when it comes from an older version. One from a newer version
+/// is read as it is, `save` then refuses to write over it.
+pub fn parse<T: Versioned>(text: &str) -> Result<T, String> {
+  let mut value: T = ron::from_str(text).map_err(|e| e.to_string())?;
+  if value.version() < T::VERSION {
+    value.migrate(value.version());
+  }
+  Ok(value)
+}
+
+/// Reads the save at `path` with `parse`, `None` when there's nothing usable: no file, or a
+/// broken one, which is worth a warning.
+pub fn load<T>(path: &Path, what: &str, parse: impl FnOnce(&str) -> Result<T, String>) -> Option<T> {
+  let text = fs::read_to_string(path).ok()?;
+  parse(&text).map_err(|message| eprintln!("Ignoring {} in {}: {}", what, path.display(), message)).ok()
+}
+
+/// Writes `value` to `path` at the current version, unless a newer version of the game wrote
+/// what's there: this one would drop whatever it doesn't know about.
+pub fn save<T: Versioned>(value: &mut T, path: &Path) -> Result<(), String> {
+  if value.version() > T::VERSION {
+    return Err(format!("{} was written by a newer version of the game, not overwriting it", path.display()));
+  }
+  if let Some(directory) = path.parent() {
+    fs::create_dir_all(directory).map_err(|e| format!("Couldn't create {}: {}", directory.display(), e))?;
+  }
+
+  value.set_version(T::VERSION);
+  let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+  fs::write(path, text).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+}
@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Deserializer};
+
+const OVERRIDE: &str = "assets/themes.ron";
+
+/// Every color the game draws with. Sprites are multiplied by `tint`.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Palette {
+  #[serde(deserialize_with = "hex")]
+  pub background: Color,
+  #[serde(deserialize_with = "hex")]
+  pub text: Color,
+  #[serde(deserialize_with = "hex")]
+  pub highlight: Color,
+  #[serde(deserialize_with = "hex")]
+  pub ground: Color,
+  #[serde(deserialize_with = "hex")]
+  pub dino: Color,
+  #[serde(deserialize_with = "hex")]
+  pub cactus: Color,
+  #[serde(deserialize_with = "hex")]
+  pub bird: Color,
+  #[serde(deserialize_with = "hex")]
+  pub tint: Color,
+  #[serde(deserialize_with = "hex")]
+  pub clouds: Color,
+  #[serde(deserialize_with = "hex")]
+  pub far_mountains: Color,
+  #[serde(deserialize_with = "hex")]
+  pub near_mountains: Color,
+  #[serde(deserialize_with = "hex")]
+  pub dust: Color
+}
+
+impl Palette {
+  /// Blends towards `other`, `t` from 0 (self) to 1 (other).
+  pub fn lerp(& self, other: &Palette, t: f32) -> Palette {
+    let mix = |a: Color, b: Color| Color::new(
+      a.r + (b.r - a.r) * t, a.g + (b.g - a.g) * t, a.b + (b.b - a.b) * t, a.a + (b.a - a.a) * t
+    );
+
+    Palette {
+      background: mix(self.background, other.background),
+      text: mix(self.text, other.text),
+      highlight: mix(self.highlight, other.highlight),
+      ground: mix(self.ground, other.ground),
+      dino: mix(self.dino, other.dino),
+      cactus: mix(self.cactus, other.cactus),
+      bird: mix(self.bird, other.bird),
+      tint: mix(self.tint, other.tint),
+      clouds: mix(self.clouds, other.clouds),
+      far_mountains: mix(self.far_mountains, other.far_mountains),
+      near_mountains: mix(self.near_mountains, other.near_mountains),
+      dust: mix(self.dust, other.dust)
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct Theme {
+  /// Score from which this theme takes over, within a cycle.
+  score: u32,
+  palette: Palette
+}
+
+/// The theme definitions, from `assets/themes.ron` when there is one, the built-in `themes.ron` otherwise.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Themes {
+  cycle: u32,
+  /// Seconds to fade from a theme to the next.
+  fade: f32,
+  themes: Vec<Theme>
+}
+
+impl Themes {
+  pub fn load() -> Self {
+    if Path::new(OVERRIDE).exists() {
+      match std::fs::read_to_string(OVERRIDE).map_err(|e| e.to_string()).and_then(|text| Self::parse(&text)) {
+        Ok(themes) => return themes,
+        Err(message) => eprintln!("Ignoring {}: {}", OVERRIDE, message)
+      }
+    }
+
+    Self::parse(include_str!("themes.ron")).expect("Invalid built-in themes")
+  }
+
+  fn parse(text: &str) -> Result<Self, String> {
+    let mut themes: Self = ron::from_str(text).map_err(|e| e.to_string())?;
+    if themes.themes.is_empty() {
+      return Err("no themes".to_owned());
+    }
+
+    themes.themes.sort_by_key(|theme| theme.score);
+    themes.cycle = themes.cycle.max(themes.themes[themes.themes.len() - 1].score + 1);
+    Ok(themes)
+  }
+
+  /// Index of the theme for a score, themes start over every cycle.
+  fn index_at(& self, score: u32) -> usize {
+    let score = score % self.cycle;
+    self.themes.iter().rposition(|theme| theme.score <= score).unwrap_or(0)
+  }
+}
+
+/// Follows the score through the themes, fading from one palette to the next.
+pub struct ThemeCycle {
+  themes: Themes,
+  current: usize,
+  from: Palette,
+  progress: f32
+}
+
+impl ThemeCycle {
+  pub fn new(themes: Themes) -> Self {
+    let from = themes.themes[0].palette;
+    Self { themes, current: 0, from, progress: 1f32 }
+  }
+
+  pub fn update(&mut self, score: u32, dt: f32) {
+    let target = self.themes.index_at(score);
+    if target != self.current {
+      // Starting from the blended colors, a switch during a fade doesn't jump.
+      self.from = self.palette();
+      self.current = target;
+      self.progress = 0f32;
+    }
+
+    self.progress = if self.themes.fade > 0f32 { (self.progress + dt / self.themes.fade).min(1f32) } else { 1f32 };
+  }
+
+  pub fn palette(& self) -> Palette {
+    let target = self.themes.themes[self.current].palette;
+    if self.progress >= 1f32 {
+      return target;
+    }
+
+    // Smoothstep, so fades ease in and out.
+    let t = self.progress * self.progress * (3f32 - 2f32 * self.progress);
+    self.from.lerp(&target, t)
+  }
+}
+
+fn hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+  use serde::de::Error;
+
+  let text = String::deserialize(deserializer)?;
+  let digits = text.strip_prefix('#').filter(|digits| digits.len() == 6 || digits.len() == 8);
+  let value = digits.and_then(|digits| u32::from_str_radix(digits, 16).ok())
+    .ok_or_else(|| D::Error::custom(format!("{} isn't a #rrggbb or #rrggbbaa color", text)))?;
+
+  let rgba = if text.len() == 7 { value << 8 | 0xff } else { value };
+  let [r, g, b, a] = rgba.to_be_bytes();
+  Ok(Color::from_rgba(r, g, b, a))
+}
+
+#[cfg(test)]
+mod tests {
+  use macroquad::prelude::Color;
+
+  use super::{ThemeCycle, Themes};
+
+  fn builtin() -> Themes {
+    Themes::parse(include_str!("themes.ron")).unwrap()
+  }
+
+  #[test]
+  fn builtin_themes_cycle_with_the_score() {
+    let themes = builtin();
+
+    assert_eq!(themes.index_at(0), 0);
+    assert_eq!(themes.index_at(450), 1);
+    assert_eq!(themes.index_at(750), 2);
+    assert_eq!(themes.index_at(themes.cycle + 10), 0);
+  }
+
+  #[test]
+  fn parses_hex_colors() {
+    let palette = builtin().themes[0].palette;
+
+    assert_eq!(palette.background, Color::from_rgba(255, 255, 255, 255));
+    assert_eq!(palette.highlight, Color::from_rgba(0xe6, 0x29, 0x37, 255));
+    assert!(Themes::parse(&include_str!("themes.ron").replace("#ffffff", "white")).is_err());
+  }
+
+  #[test]
+  fn fades_between_themes() {
+    let themes = builtin();
+    let (day, night) = (themes.themes[0].palette, themes.themes[2].palette);
+    let fade = themes.fade;
+    let mut cycle = ThemeCycle::new(themes);
+
+    cycle.update(700, 0f32);
+    assert_eq!(cycle.palette(), day);
+
+    cycle.update(700, fade * 0.5f32);
+    let halfway = cycle.palette().background;
+    assert!(halfway.r < day.background.r && halfway.r > night.background.r);
+
+    cycle.update(710, fade);
+    assert_eq!(cycle.palette(), night);
+  }
+}
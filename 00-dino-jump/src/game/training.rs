@@ -0,0 +1,203 @@
+use std::path::Path;
+use std::thread;
+
+use super::{CANVAS, TICKS_PER_SECOND};
+use super::controller::Controller;
+use super::difficulty::Preset;
+use super::genome::{Genome, NeuralBot, WEIGHTS};
+use super::rng::Rng;
+use super::simulation::{Simulation, State};
+
+// Chance for each weight of a child to be nudged, and the standard deviation of the nudge.
+const MUTATION_RATE: f32 = 0.1f32;
+const MUTATION_SIZE: f32 = 0.3f32;
+// Genomes drawn for each tournament, the fittest of them becomes a parent.
+const TOURNAMENT_SIZE: usize = 3;
+
+/// Sizes of a training run.
+#[derive(Clone, Copy)]
+pub struct Config {
+  pub population: usize,
+  /// The best genomes of a generation, copied unchanged into the next.
+  pub elites: usize,
+  /// Games each genome plays per generation, always on the same seeds.
+  pub seeds: u64,
+  /// Games still going after this long stop there.
+  pub max_seconds: u64
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self { population: 50, elites: 5, seeds: 8, max_seconds: 60 }
+  }
+}
+
+/// How a generation did.
+pub struct Generation {
+  pub number: u32,
+  pub best: f32,
+  pub mean: f32
+}
+
+/// Evolves the weights of [`NeuralBot`] networks by playing headless games.
+///
+/// Every genome plays the same seeds each generation and the elites survive unchanged, so the
+/// best fitness never goes down.
+pub struct Trainer {
+  config: Config,
+  preset: Preset,
+  first_seed: u64,
+  rng: Rng,
+  population: Vec<Genome>,
+  generation: u32
+}
+
+impl Trainer {
+  pub fn new(config: Config, first_seed: u64, preset: Preset) -> Self {
+    let mut rng = Rng::new(first_seed);
+    let population = (0..config.population)
+      .map(|_| Genome::new((0..WEIGHTS).map(|_| rng.gen_normal()).collect()))
+      .collect();
+
+    Self { config, preset, first_seed, rng, population, generation: 0 }
+  }
+
+  /// Scores the current population, then breeds the next one. Returns the best genome found.
+  pub fn evolve(&mut self) -> (Generation, Genome) {
+    let fitness = self.evaluate();
+    let mut ranked: Vec<(f32, Genome)> = fitness.into_iter().zip(self.population.drain(..)).collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    self.generation += 1;
+    let generation = Generation {
+      number: self.generation,
+      best: ranked[0].0,
+      mean: ranked.iter().map(|(fitness, _)| fitness).sum::<f32>() / ranked.len() as f32
+    };
+
+    let mut next: Vec<Genome> = ranked.iter().take(self.config.elites.max(1)).map(|(_, genome)| genome.clone()).collect();
+    while next.len() < self.config.population {
+      let (a, b) = (self.select(&ranked), self.select(&ranked));
+      let child = self.crossover(a, b);
+      next.push(self.mutate(child));
+    }
+    self.population = next;
+
+    (generation, ranked.swap_remove(0).1)
+  }
+
+  /// Fitness of every genome, with the games spread over all cores.
+  fn evaluate(& self) -> Vec<f32> {
+    let threads = thread::available_parallelism().map_or(1, |count| count.get());
+    let chunk = self.population.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+      let handles: Vec<_> = self.population.chunks(chunk)
+        .map(|genomes| scope.spawn(move || genomes.iter().map(|genome| self.fitness(genome)).collect::<Vec<f32>>()))
+        .collect();
+      handles.into_iter().flat_map(|handle| handle.join().expect("A training thread panicked")).collect()
+    })
+  }
+
+  /// Points plus seconds survived, averaged over the training seeds.
+  fn fitness(& self, genome: &Genome) -> f32 {
+    let max_ticks = self.config.max_seconds * TICKS_PER_SECOND as u64;
+    let mut total = 0f32;
+
+    for seed in (0..self.config.seeds).map(|run| self.first_seed.wrapping_add(run)) {
+      let mut simulation = Simulation::new(CANVAS, seed);
+      simulation.set_preset(self.preset);
+      let mut bot = NeuralBot::new(genome.clone());
+
+      while simulation.state() == State::Playing && simulation.ticks() < max_ticks {
+        let input = bot.input(&simulation);
+        simulation.step(&input);
+      }
+
+      total += simulation.score() as f32 + (simulation.ticks() / TICKS_PER_SECOND as u64) as f32;
+    }
+
+    total / self.config.seeds.max(1) as f32
+  }
+
+  fn select<'a>(&mut self, ranked: &'a [(f32, Genome)]) -> &'a Genome {
+    // Ranked best first, so the lowest index drawn wins.
+    let winner = (0..TOURNAMENT_SIZE).map(|_| self.rng.gen_range(0, ranked.len() as u32) as usize).min().unwrap_or(0);
+    &ranked[winner].1
+  }
+
+  /// Takes each weight from either parent.
+  fn crossover(&mut self, a: &Genome, b: &Genome) -> Genome {
+    Genome::new(a.weights.iter().zip(&b.weights)
+      .map(|(&a, &b)| if self.rng.gen_float() < 0.5f32 { a } else { b })
+      .collect())
+  }
+
+  fn mutate(&mut self, mut genome: Genome) -> Genome {
+    for weight in genome.weights.iter_mut() {
+      if self.rng.gen_float() < MUTATION_RATE {
+        *weight += self.rng.gen_normal() * MUTATION_SIZE;
+      }
+    }
+    genome
+  }
+}
+
+/// Trains for `generations` generations, saving the best genome to `path` after each of them.
+pub fn run(path: &Path, generations: u32, first_seed: u64, preset: Preset) -> Result<(), String> {
+  if generations == 0 {
+    return Err("Training needs at least one generation".to_owned());
+  }
+  let config = Config::default();
+  println!("Training {} genomes on {}, seeds {} to {}", config.population, preset.name(), first_seed, first_seed.wrapping_add(config.seeds - 1));
+
+  let mut trainer = Trainer::new(config, first_seed, preset);
+  for _ in 0..generations {
+    let (generation, best) = trainer.evolve();
+    println!("Generation {:>4}: best {:.1}, mean {:.1}", generation.number, generation.best, generation.mean);
+    best.save(path)?;
+  }
+
+  // Only reached once every generation got saved.
+  println!("Best genome saved to {}", path.display());
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::{run, Config, Trainer};
+  use crate::game::difficulty::Preset;
+
+  #[test]
+  fn the_best_never_gets_worse() {
+    let config = Config { population: 12, elites: 2, seeds: 2, max_seconds: 10 };
+    let mut trainer = Trainer::new(config, 3, Preset::Normal);
+
+    let mut best = 0f32;
+    for _ in 0..4 {
+      let (generation, _) = trainer.evolve();
+      assert!(generation.best >= best, "best went from {} to {}", best, generation.best);
+      assert!(generation.mean <= generation.best);
+      best = generation.best;
+    }
+  }
+
+  #[test]
+  fn zero_generations_are_rejected() {
+    assert!(run(Path::new("never-written.ron"), 0, 1, Preset::Normal).is_err());
+    assert!(!Path::new("never-written.ron").exists());
+  }
+
+  #[test]
+  fn training_is_reproducible() {
+    let config = Config { population: 6, elites: 1, seeds: 1, max_seconds: 5 };
+    let mut a = Trainer::new(config, 9, Preset::Easy);
+    let mut b = Trainer::new(config, 9, Preset::Easy);
+
+    for _ in 0..2 {
+      assert_eq!(a.evolve().1, b.evolve().1);
+    }
+  }
+}
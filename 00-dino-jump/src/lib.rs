@@ -0,0 +1,6 @@
+//! The game and its headless simulation, shared by the executable and the integration tests.
+
+mod animation;
+pub mod game;
+pub mod options;
+mod parallax;
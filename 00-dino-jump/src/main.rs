@@ -1,15 +1,81 @@
 use macroquad::{prelude::*};
 
-mod game;
+use dino_jump::game;
+use game::benchmark;
+use game::bot::Bot;
+use game::genome::{Genome, NeuralBot};
+use game::replay::{self, Replay};
+use game::stats::Stats;
+use game::training;
+use dino_jump::options::Options;
 
-#[macroquad::main("BasicShapes")]
-async fn main() {
-  let mut context = game::Context::new();
+fn main() {
+  let options = match Options::from_env() {
+    Ok(options) => options,
+    Err(message) => exit_with(&message)
+  };
+  
+  // Checking a replay, exporting stats, benchmarking the bot or training a network doesn't need a window.
+  if let Some(path) = &options.verify {
+    match Replay::load(path).and_then(|replay| replay::verify(&replay)) {
+      Ok(score) => println!("Replay verified: {} points", score),
+      Err(message) => exit_with(&message)
+    }
+    return;
+  }
+  
+  if let Some(path) = &options.export_stats {
+    match Stats::load().export(path) {
+      Ok(runs) => println!("{} runs exported to {}", runs, path.display()),
+      Err(message) => exit_with(&message)
+    }
+    return;
+  }
+  
+  if options.profile {
+    println!("{}", benchmark::profile());
+    return;
+  }
+  
+  let genome = options.genome.as_ref().map(|path| Genome::load(path).unwrap_or_else(|message| exit_with(&message)));
+  
+  if options.headless {
+    let first_seed = options.seed.unwrap_or_else(game::random_seed);
+    let report = match &genome {
+      Some(genome) => benchmark::run(options.runs, first_seed, options.difficulty, || Box::new(NeuralBot::new(genome.clone()))),
+      None => benchmark::run(options.runs, first_seed, options.difficulty, || Box::new(Bot::default()))
+    };
+    println!("{}", report);
+    return;
+  }
+  
+  if let Some(path) = &options.train {
+    let first_seed = options.seed.unwrap_or_else(game::random_seed);
+    if let Err(message) = training::run(path, options.generations, first_seed, options.difficulty) {
+      exit_with(&message);
+    }
+    return;
+  }
+  
+  let replay = options.replay.as_ref().map(|path| Replay::load(path).unwrap_or_else(|message| exit_with(&message)));
+  let opponent = options.opponent.as_ref().map(|path| Genome::load(path).unwrap_or_else(|message| exit_with(&message)));
+  
+  macroquad::Window::new("Dino Jump", run(options, replay, genome, opponent));
+}
+
+async fn run(options: Options, replay: Option<Replay>, genome: Option<Genome>, opponent: Option<Genome>) {
+  let sprites = game::sprites::Sprites::load().await;
+  let audio = game::audio::Audio::load().await;
+  let mut context = game::Context::new(&options, replay, genome, opponent, sprites, audio);
   
   loop {
     context.update();
-    clear_background(WHITE);
     context.draw();
     next_frame().await
   }
-}
\ No newline at end of file
+}
+
+fn exit_with(message: &str) -> ! {
+  eprintln!("{}", message);
+  std::process::exit(1);
+}
@@ -0,0 +1,94 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::game::difficulty::Preset;
+
+const DEFAULT_RUNS: u32 = 100;
+const DEFAULT_GENERATIONS: u32 = 100;
+
+/// Command-line flags, some of them also readable from the environment.
+pub struct Options {
+  pub seed: Option<u64>,
+  pub record: Option<PathBuf>,
+  pub replay: Option<PathBuf>,
+  pub verify: Option<PathBuf>,
+  /// Lets the bot play instead of the keyboard.
+  pub bot: bool,
+  /// Runs the bot without a window, `runs` times, and reports the scores.
+  pub headless: bool,
+  pub runs: u32,
+  /// Times a tick against the number of obstacles on screen.
+  pub profile: bool,
+  /// Evolves a network for `generations` generations and saves the best genome there.
+  pub train: Option<PathBuf>,
+  pub generations: u32,
+  /// Lets a trained network play instead of the keyboard.
+  pub genome: Option<PathBuf>,
+  /// Races a trained network in versus, in place of player 2.
+  pub opponent: Option<PathBuf>,
+  /// Writes the player's recorded runs there as CSV.
+  pub export_stats: Option<PathBuf>,
+  /// Preset of headless runs and training, the game itself takes it from the settings.
+  pub difficulty: Preset
+}
+
+impl Options {
+  pub fn from_env() -> Result<Self, String> {
+    let mut options = Self {
+      seed: match env::var("DINO_SEED") {
+        Ok(value) => Some(parse_seed(&value)?),
+        Err(_) => None
+      },
+      record: None,
+      replay: None,
+      verify: None,
+      bot: false,
+      headless: false,
+      runs: DEFAULT_RUNS,
+      profile: false,
+      train: None,
+      generations: DEFAULT_GENERATIONS,
+      genome: None,
+      opponent: None,
+      export_stats: None,
+      difficulty: Preset::default()
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+      let mut value = || args.next().ok_or(format!("{} expects a value", arg));
+      match arg.as_str() {
+        "--seed" => options.seed = Some(parse_seed(&value()?)?),
+        "--record" => options.record = Some(PathBuf::from(value()?)),
+        "--replay" => options.replay = Some(PathBuf::from(value()?)),
+        "--verify" => options.verify = Some(PathBuf::from(value()?)),
+        "--bot" => options.bot = true,
+        "--headless" => options.headless = true,
+        "--profile" => options.profile = true,
+        "--runs" => {
+          let runs = value()?;
+          options.runs = runs.trim().parse().map_err(|_| format!("Invalid number of runs '{}'", runs))?;
+        },
+        "--train" => options.train = Some(PathBuf::from(value()?)),
+        "--generations" => {
+          let generations = value()?;
+          options.generations = generations.trim().parse().map_err(|_| format!("Invalid number of generations '{}'", generations))?;
+        },
+        "--genome" => options.genome = Some(PathBuf::from(value()?)),
+        "--opponent" => options.opponent = Some(PathBuf::from(value()?)),
+        "--export-stats" => options.export_stats = Some(PathBuf::from(value()?)),
+        "--difficulty" => {
+          let name = value()?;
+          options.difficulty = Preset::parse(&name).ok_or(format!("Unknown difficulty '{}', expected easy, normal or hard", name))?;
+        },
+        _ => return Err(format!("Unknown argument '{}'", arg))
+      }
+    }
+
+    Ok(options)
+  }
+}
+
+fn parse_seed(value: &str) -> Result<u64, String> {
+  value.trim().parse().map_err(|_| format!("Invalid seed '{}'", value))
+}
@@ -0,0 +1,122 @@
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+
+/// What a layer is made of, generated at random along a tile as wide as the view.
+#[derive(Clone, Copy)]
+pub enum Content {
+  /// Peaks standing on `baseline`, up to `max_height` tall.
+  Mountains { baseline: f32, max_height: f32 },
+  /// Puffs floating between `top` and `bottom`.
+  Clouds { top: f32, bottom: f32 },
+  /// Specks scattered between `top` and `bottom`, like sand on the ground.
+  Dots { top: f32, bottom: f32 }
+}
+
+#[derive(Clone, Copy)]
+pub struct LayerConfig {
+  /// How fast the layer moves compared to the world, 1.0 scrolls with it.
+  pub scroll_factor: f32,
+  pub content: Content,
+  /// Number of shapes per tile.
+  pub density: usize,
+  pub color: Color
+}
+
+#[derive(Clone, Copy)]
+enum Shape {
+  Peak { x: f32, baseline: f32, half_width: f32, height: f32 },
+  Puff { x: f32, y: f32, radius: f32 },
+  Speck { x: f32, y: f32, size: f32 }
+}
+
+impl Shape {
+  fn generate(content: &Content, width: f32) -> Self {
+    let x = gen_range(0f32, width);
+    match *content {
+      Content::Mountains { baseline, max_height } => {
+        let height = gen_range(max_height * 0.4f32, max_height);
+        Shape::Peak { x, baseline, half_width: height * gen_range(1f32, 2f32), height }
+      },
+      Content::Clouds { top, bottom } => Shape::Puff { x, y: gen_range(top, bottom), radius: gen_range(12f32, 24f32) },
+      Content::Dots { top, bottom } => Shape::Speck { x, y: gen_range(top, bottom), size: gen_range(1f32, 4f32) }
+    }
+  }
+
+  fn draw(& self, offset: f32, color: Color) {
+    match *self {
+      Shape::Peak { x, baseline, half_width, height } => {
+        let x = x + offset;
+        draw_triangle(vec2(x - half_width, baseline), vec2(x + half_width, baseline), vec2(x, baseline - height), color);
+      },
+      Shape::Puff { x, y, radius } => {
+        let x = x + offset;
+        draw_circle(x, y, radius, color);
+        draw_circle(x + radius, y + radius * 0.3f32, radius * 0.8f32, color);
+        draw_circle(x - radius, y + radius * 0.3f32, radius * 0.7f32, color);
+      },
+      Shape::Speck { x, y, size } => draw_rectangle(x + offset, y, size, size, color)
+    }
+  }
+
+  // Horizontal span of the shape, as (x, how far it reaches on each side).
+  fn span(& self) -> (f32, f32) {
+    match *self {
+      Shape::Peak { x, half_width, .. } => (x, half_width),
+      Shape::Puff { x, radius, .. } => (x, radius * 2f32),
+      Shape::Speck { x, size, .. } => (x, size)
+    }
+  }
+}
+
+struct Layer {
+  config: LayerConfig,
+  shapes: Vec<Shape>,
+  offset: f32
+}
+
+/// Layers of procedural scenery scrolling at their own pace, drawn back to front.
+pub struct Parallax {
+  layers: Vec<Layer>,
+  width: f32
+}
+
+impl Parallax {
+  pub fn new(configs: &[LayerConfig], width: f32) -> Self {
+    let layers = configs.iter().map(|config| Layer {
+      config: *config,
+      shapes: (0..config.density).map(|_| Shape::generate(&config.content, width)).collect(),
+      offset: 0f32
+    }).collect();
+
+    Self { layers, width }
+  }
+
+  /// Moves every layer by its share of `distance`, the distance the world scrolled.
+  pub fn scroll(&mut self, distance: f32) {
+    for layer in self.layers.iter_mut() {
+      layer.offset = (layer.offset + distance * layer.config.scroll_factor) % self.width;
+    }
+  }
+
+  /// Repaints the layers, one color per layer in the order they were configured.
+  pub fn recolor(&mut self, colors: &[Color]) {
+    for (layer, color) in self.layers.iter_mut().zip(colors) {
+      layer.config.color = *color;
+    }
+  }
+
+  pub fn draw(& self) {
+    for layer in self.layers.iter() {
+      for shape in layer.shapes.iter() {
+        // Tiles repeat, so a shape leaving on the left comes back on the right.
+        let (x, reach) = shape.span();
+        for tile in [-self.width, 0f32, self.width] {
+          let offset = tile - layer.offset;
+          if x + offset + reach >= 0f32 && x + offset - reach <= self.width {
+            shape.draw(offset, layer.config.color);
+          }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,53 @@
+//! Once a run is going, a tick shouldn't allocate: obstacles and power-ups live in pools
+//! and every buffer has reached its final size. The allocator counts for the whole process,
+//! so this runs on its own, without the test harness and its threads.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dino_jump::game::{CANVAS, TICKS_PER_SECOND};
+use dino_jump::game::bot::Bot;
+use dino_jump::game::controller::Controller;
+use dino_jump::game::simulation::{Simulation, State};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+    System.dealloc(pointer, layout)
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() {
+  let mut simulation = Simulation::new(CANVAS, 1234);
+  let mut bot = Bot::default();
+  let mut step = |simulation: &mut Simulation| {
+    let input = bot.input(simulation);
+    simulation.step(&input);
+  };
+
+  // Long enough for every buffer to reach its final size.
+  for _ in 0..30 * TICKS_PER_SECOND {
+    step(&mut simulation);
+  }
+
+  let before = ALLOCATIONS.load(Ordering::Relaxed);
+  for _ in 0..90 * TICKS_PER_SECOND {
+    step(&mut simulation);
+  }
+  let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+  assert_eq!(simulation.state(), State::Playing);
+  assert_eq!(allocations, 0, "{} allocations in 90 seconds of play", allocations);
+  println!("steady state: no allocations in 90 seconds of play");
+}